@@ -42,7 +42,7 @@ pub struct Manufacturer {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(super) struct FuelDefinition {
     pub fuel: ItemAmountDefinition,
-    #[serde(default)]
+    #[serde(default, alias = "supplemental_item")]
     pub supplemental: Option<ItemAmountDefinition>,
     #[serde(default)]
     pub by_product: Option<ItemAmountDefinition>,
@@ -54,6 +54,7 @@ pub struct Fuel {
     pub fuel: ItemPerMinute,
     pub supplemental: Option<ItemPerMinute>,
     pub by_product: Option<ItemPerMinute>,
+    #[allow(dead_code)]
     pub burn_time_secs: FloatType,
 }
 
@@ -92,9 +93,11 @@ pub(super) struct ResourceExtractorDefinition {
 pub struct ResourceExtractor {
     pub key: String,
     pub name: String,
+    #[allow(dead_code)]
     pub power_consumption: PowerConsumption,
     pub extraction_rate: FloatType,
     pub allowed_resources: Vec<Rc<Item>>,
+    #[allow(dead_code)]
     pub extractor_type: Option<String>,
     pub dimensions: Option<Dimensions>,
 }
@@ -123,9 +126,11 @@ pub struct ResourceWellDefinition {
 pub struct ResourceWell {
     pub key: String,
     pub name: String,
+    #[allow(dead_code)]
     pub power_consumption: PowerConsumption,
     pub allowed_resources: Vec<Rc<Item>>,
     pub satellite_buildings: Vec<ResourceWellExtractor>,
+    #[allow(dead_code)]
     pub extractor_type: Option<String>,
     pub dimensions: Option<Dimensions>,
 }
@@ -144,7 +149,9 @@ pub struct ItemProducerDefinition {
 pub struct ItemProducer {
     pub key: String,
     pub name: String,
+    #[allow(dead_code)]
     pub power_consumption: PowerConsumption,
+    #[allow(dead_code)]
     pub craft_time_secs: FloatType,
     pub output: ItemPerMinute,
     pub dimensions: Option<Dimensions>,
@@ -165,6 +172,18 @@ pub(super) enum BuildingDefinition {
     ItemProducer(ItemProducerDefinition),
 }
 
+impl BuildingDefinition {
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Manufacturer(m) => &m.key,
+            Self::PowerGenerator(pg) => &pg.key,
+            Self::ResourceExtractor(re) => &re.key,
+            Self::ResourceWell(rw) => &rw.key,
+            Self::ItemProducer(ip) => &ip.key,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Building {
     Manufacturer(Manufacturer),
@@ -247,7 +266,7 @@ impl Building {
     }
 
     pub fn is_power_generator(&self) -> bool {
-        matches!(self, Self::Manufacturer(..))
+        matches!(self, Self::PowerGenerator(..))
     }
 
     pub fn as_power_generator(&self) -> &PowerGenerator {
@@ -260,7 +279,7 @@ impl Building {
     }
 
     pub fn is_resource_extractor(&self) -> bool {
-        matches!(self, Self::Manufacturer(..))
+        matches!(self, Self::ResourceExtractor(..))
     }
 
     pub fn as_resource_extractor(&self) -> &ResourceExtractor {
@@ -319,6 +338,26 @@ impl PartialEq for Building {
 
 impl Eq for Building {}
 
+impl ResourceWell {
+    /// The combined extraction rate of every satellite extractor on the well
+    pub fn total_extraction_rate(&self) -> FloatType {
+        self.satellite_buildings
+            .iter()
+            .map(|satellite| satellite.extraction_rate)
+            .sum()
+    }
+}
+
+impl Fuel {
+    pub fn find_input_by_item(&self, item: &Item) -> Option<&ItemPerMinute> {
+        if *self.fuel.item == *item {
+            Some(&self.fuel)
+        } else {
+            self.supplemental.as_ref().filter(|s| *s.item == *item)
+        }
+    }
+}
+
 #[allow(dead_code)]
 impl Dimensions {
     pub fn volume(&self) -> FloatType {
@@ -368,3 +407,36 @@ impl fmt::Display for PowerConsumption {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::game::test::get_test_game_db;
+
+    #[test]
+    fn building_type_predicates() {
+        let game_db = get_test_game_db();
+
+        let buildings = [
+            "Desc_AssemblerMk1_C",
+            "Desc_GeneratorBiomass_C",
+            "Desc_MinerMk1_C",
+            "Desc_TreeGiftProducer_C",
+            "Desc_FrackingSmasher_C",
+        ];
+
+        for (i, key) in buildings.iter().enumerate() {
+            let building = game_db.find_building(key).unwrap();
+            let predicates = [
+                building.is_manufacturer(),
+                building.is_power_generator(),
+                building.is_resource_extractor(),
+                building.is_item_producer(),
+                building.is_resource_well(),
+            ];
+
+            for (j, predicate) in predicates.iter().enumerate() {
+                assert_eq!(*predicate, i == j, "{} predicate {}", key, j);
+            }
+        }
+    }
+}
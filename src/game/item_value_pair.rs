@@ -60,11 +60,7 @@ impl Eq for ItemPerMinute {}
 
 impl PartialOrd for ItemPerMinute {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(
-            self.item
-                .cmp(&other.item)
-                .then_with(|| self.amount.total_cmp(&other.amount)),
-        )
+        Some(self.cmp(other))
     }
 }
 
@@ -0,0 +1,66 @@
+use serde::Deserialize;
+
+use super::ItemState;
+use crate::utils::FloatType;
+
+#[derive(Debug, Copy, Clone, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BeltTier {
+    Mk1,
+    Mk2,
+    Mk3,
+    Mk4,
+    #[default]
+    Mk5,
+    Mk6,
+}
+
+#[derive(Debug, Copy, Clone, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PipeTier {
+    Mk1,
+    #[default]
+    Mk2,
+}
+
+impl BeltTier {
+    pub const HIGHEST: Self = Self::Mk6;
+
+    /// Items per minute a single belt of this tier can carry
+    pub fn throughput(&self) -> FloatType {
+        match self {
+            Self::Mk1 => 60.0,
+            Self::Mk2 => 120.0,
+            Self::Mk3 => 270.0,
+            Self::Mk4 => 480.0,
+            Self::Mk5 => 780.0,
+            Self::Mk6 => 1200.0,
+        }
+    }
+}
+
+impl PipeTier {
+    pub const HIGHEST: Self = Self::Mk2;
+
+    /// Cubic meters per minute a single pipeline of this tier can carry
+    pub fn throughput(&self) -> FloatType {
+        match self {
+            Self::Mk1 => 300.0,
+            Self::Mk2 => 600.0,
+        }
+    }
+}
+
+/// The throughput of a single belt, or pipeline for fluids, carrying an item in the
+/// given state
+pub fn conveyor_throughput(
+    state: ItemState,
+    belt_tier: BeltTier,
+    pipe_tier: PipeTier,
+) -> FloatType {
+    if state.is_fluid() {
+        pipe_tier.throughput()
+    } else {
+        belt_tier.throughput()
+    }
+}
@@ -1,24 +1,35 @@
 pub mod building;
 pub mod item;
 pub mod item_value_pair;
+pub mod logistics;
+pub mod presets;
 pub mod recipe;
 
+use anyhow::bail;
 use recipe::RecipeDefinition;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs::File, path::Path, rc::Rc};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    path::Path,
+    rc::Rc,
+};
 use thiserror::Error;
 
-pub use building::{Building, Dimensions, PowerConsumption};
+pub use building::{Building, Fuel, PowerConsumption};
 pub use item::{Item, ItemState};
 pub use item_value_pair::ItemPerMinute;
-pub use recipe::Recipe;
+pub use presets::ResourcePreset;
+pub use recipe::{ItemPerCraft, Recipe, RecipePower};
+
+use crate::utils::{closest_matches, FloatType};
 
-use crate::utils::FloatType;
+/// Recipes whose inputs are worth this many times more sink points than their outputs
+/// are flagged as a likely data error
+const SINK_POINTS_IMBALANCE_RATIO: FloatType = 10.0;
 
 use self::{
-    building::{
-        BuildingDefinition, Fuel, ItemProducer, PowerGenerator, ResourceExtractor, ResourceWell,
-    },
+    building::{BuildingDefinition, ItemProducer, PowerGenerator, ResourceExtractor, ResourceWell},
     item_value_pair::ItemAmountDefinition,
 };
 
@@ -38,17 +49,61 @@ pub enum GameDatabaseError {
     UnknownBuildingKey(String),
     #[error("Recipe `{0}: Building `{1}` is not a manufacturer.")]
     NotAManufacturer(String, String),
+    #[error("Miner Mk.{0}: No such miner exists.")]
+    UnknownMinerTier(u8),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct GameDatabaseDefinition {
+    #[serde(default)]
     by_product_blacklist: Vec<String>,
+    #[serde(default)]
     items: Vec<Rc<Item>>,
+    #[serde(default)]
     buildings: Vec<BuildingDefinition>,
+    #[serde(default)]
     recipes: Vec<RecipeDefinition>,
+    #[serde(default)]
     resource_limits: HashMap<String, FloatType>,
 }
 
+impl GameDatabaseDefinition {
+    fn merge(&mut self, other: GameDatabaseDefinition) {
+        for item_key in other.by_product_blacklist {
+            if !self.by_product_blacklist.contains(&item_key) {
+                self.by_product_blacklist.push(item_key);
+            }
+        }
+
+        for item in other.items {
+            match self.items.iter_mut().find(|i| i.key == item.key) {
+                Some(existing) => *existing = item,
+                None => self.items.push(item),
+            }
+        }
+
+        for building in other.buildings {
+            match self
+                .buildings
+                .iter_mut()
+                .find(|b| b.key() == building.key())
+            {
+                Some(existing) => *existing = building,
+                None => self.buildings.push(building),
+            }
+        }
+
+        for recipe in other.recipes {
+            match self.recipes.iter_mut().find(|r| r.key == recipe.key) {
+                Some(existing) => *existing = recipe,
+                None => self.recipes.push(recipe),
+            }
+        }
+
+        self.resource_limits.extend(other.resource_limits);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GameDatabase {
     pub by_product_blacklist: Vec<Rc<Item>>,
@@ -67,7 +122,84 @@ impl GameDatabase {
         Ok(Self::convert(config)?)
     }
 
+    /// Loads and merges several game databases, such as the base game followed by mod
+    /// content.  Items, buildings and recipes in later files replace those with the same
+    /// key in earlier files.
+    pub fn from_files<P: AsRef<Path>>(file_paths: &[P]) -> Result<GameDatabase, anyhow::Error> {
+        let mut merged: Option<GameDatabaseDefinition> = None;
+        for file_path in file_paths {
+            let file = File::open(file_path)?;
+            let definition: GameDatabaseDefinition = serde_yaml::from_reader(file)?;
+            Self::validate_unique_recipe_keys(&definition)?;
+
+            merged = Some(match merged {
+                Some(mut merged) => {
+                    merged.merge(definition);
+                    merged
+                }
+                None => definition,
+            });
+        }
+
+        match merged {
+            Some(definition) => Ok(Self::convert(definition)?),
+            None => bail!("At least one game database file is required"),
+        }
+    }
+
+    /// Loads the game database along with warnings about likely data errors that don't
+    /// prevent it from being used.
+    pub fn from_file_checked<P: AsRef<Path>>(
+        file_path: P,
+    ) -> Result<(GameDatabase, Vec<String>), anyhow::Error> {
+        let game_db = Self::from_file(file_path)?;
+        let warnings = game_db.find_warnings();
+
+        Ok((game_db, warnings))
+    }
+
+    pub fn find_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        for recipe in &self.recipes {
+            let input_points = total_sink_points(&recipe.inputs);
+            let output_points = total_sink_points(&recipe.outputs);
+            if output_points > 0.0 && input_points > output_points * SINK_POINTS_IMBALANCE_RATIO {
+                warnings.push(format!(
+                    "Recipe `{}`: Inputs are worth {} sink points but outputs are only worth {}",
+                    recipe.key, input_points, output_points
+                ));
+            }
+
+            for input in &recipe.inputs {
+                if !self.can_supply(&input.item) {
+                    warnings.push(format!(
+                        "Recipe `{}`: No recipe or building can supply the input `{}`",
+                        recipe.key, input.item.key
+                    ));
+                }
+            }
+        }
+
+        warnings
+    }
+
+    fn can_supply(&self, item: &Item) -> bool {
+        item.resource
+            || self.recipes.iter().any(|r| r.has_output_item(item))
+            || !self.find_item_producers(item).is_empty()
+            || self.buildings.iter().any(|b| match b.as_ref() {
+                Building::PowerGenerator(pg) => pg
+                    .fuels
+                    .iter()
+                    .any(|f| f.by_product.as_ref().is_some_and(|bp| *bp.item == *item)),
+                _ => false,
+            })
+    }
+
     fn convert(definition: GameDatabaseDefinition) -> Result<GameDatabase, GameDatabaseError> {
+        Self::validate_unique_recipe_keys(&definition)?;
+
         // validate the items in by_product_blacklist
         let mut by_product_blacklist = Vec::new();
         for item_key in &definition.by_product_blacklist {
@@ -96,10 +228,6 @@ impl GameDatabase {
 
         let mut recipes = Vec::with_capacity(definition.recipes.len());
         for recipe in definition.recipes {
-            if recipes.iter().any(|r: &Rc<Recipe>| r.key == recipe.key) {
-                return Err(GameDatabaseError::DuplicateRecipeKey(recipe.key.clone()));
-            }
-
             recipes.push(Self::convert_recipe(recipe, &buildings, &definition.items)?);
         }
 
@@ -112,6 +240,18 @@ impl GameDatabase {
         })
     }
 
+    fn validate_unique_recipe_keys(
+        definition: &GameDatabaseDefinition,
+    ) -> Result<(), GameDatabaseError> {
+        for (i, recipe) in definition.recipes.iter().enumerate() {
+            if definition.recipes[..i].iter().any(|r| r.key == recipe.key) {
+                return Err(GameDatabaseError::DuplicateRecipeKey(recipe.key.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
     fn convert_building(
         building: BuildingDefinition,
         items: &[Rc<Item>],
@@ -211,18 +351,30 @@ impl GameDatabase {
             return Err(GameDatabaseError::MissingRecipeOutputs(recipe.key.clone()));
         }
 
-        let crafts_per_min = 60.0 / recipe.craft_time_secs;
-        let inputs = recipe
+        let inputs_per_craft = recipe
             .inputs
             .iter()
-            .map(|i| Self::convert_item_amount(i, crafts_per_min, items))
-            .collect::<Result<Vec<ItemPerMinute>, GameDatabaseError>>()?;
+            .map(|i| Self::convert_item_per_craft(i, items))
+            .collect::<Result<Vec<ItemPerCraft>, GameDatabaseError>>()?;
 
-        let outputs = recipe
+        let outputs_per_craft = recipe
             .outputs
             .iter()
-            .map(|o| Self::convert_item_amount(o, crafts_per_min, items))
-            .collect::<Result<Vec<ItemPerMinute>, GameDatabaseError>>()?;
+            .map(|o| Self::convert_item_per_craft(o, items))
+            .collect::<Result<Vec<ItemPerCraft>, GameDatabaseError>>()?;
+
+        let inputs = inputs_per_craft
+            .iter()
+            .map(|i| {
+                ItemPerMinute::new(Rc::clone(&i.item), i.amount * 60.0 / recipe.craft_time_secs)
+            })
+            .collect();
+        let outputs = outputs_per_craft
+            .iter()
+            .map(|o| {
+                ItemPerMinute::new(Rc::clone(&o.item), o.amount * 60.0 / recipe.craft_time_secs)
+            })
+            .collect();
 
         Ok(Rc::new(Recipe {
             key: recipe.key,
@@ -230,13 +382,26 @@ impl GameDatabase {
             alternate: recipe.alternate,
             inputs,
             outputs,
+            inputs_per_craft,
+            outputs_per_craft,
             craft_time_secs: recipe.craft_time_secs,
             events: recipe.events,
+            tags: recipe.tags,
             building,
             power: recipe.power,
         }))
     }
 
+    fn convert_item_per_craft(
+        item_amount: &ItemAmountDefinition,
+        items: &[Rc<Item>],
+    ) -> Result<ItemPerCraft, GameDatabaseError> {
+        Ok(ItemPerCraft {
+            item: Self::find_item_by_key(&item_amount.item, items)?,
+            amount: item_amount.amount,
+        })
+    }
+
     pub fn convert_item_amount(
         item_amount: &ItemAmountDefinition,
         cycles_per_min: FloatType,
@@ -303,6 +468,52 @@ impl GameDatabase {
             .cloned()
     }
 
+    /// Finds every item whose name matches case-insensitively.  An exact key match is
+    /// never ambiguous so it is returned on its own.
+    pub fn find_items(&self, name_or_key: &str) -> Vec<Rc<Item>> {
+        if let Some(item) = self.items.iter().find(|i| i.key == name_or_key) {
+            return vec![item.clone()];
+        }
+
+        self.items
+            .iter()
+            .filter(|i| i.name.eq_ignore_ascii_case(name_or_key))
+            .cloned()
+            .collect()
+    }
+
+    /// The names of the items whose name or key is closest to a mistyped name or key
+    pub fn suggest_items(&self, name_or_key: &str) -> Vec<String> {
+        let matches = closest_matches(
+            name_or_key,
+            self.items
+                .iter()
+                .flat_map(|i| [i.name.as_str(), i.key.as_str()]),
+        );
+        unique_names(
+            matches
+                .iter()
+                .filter_map(|m| self.find_item(m))
+                .map(|i| i.name.clone()),
+        )
+    }
+
+    /// The names of the recipes whose name or key is closest to a mistyped name or key
+    pub fn suggest_recipes(&self, name_or_key: &str) -> Vec<String> {
+        let matches = closest_matches(
+            name_or_key,
+            self.recipes
+                .iter()
+                .flat_map(|r| [r.name.as_str(), r.key.as_str()]),
+        );
+        unique_names(
+            matches
+                .iter()
+                .filter_map(|m| self.find_recipe(m))
+                .map(|r| r.name.clone()),
+        )
+    }
+
     #[inline]
     pub fn find_building(&self, name_or_key: &str) -> Option<Rc<Building>> {
         self.buildings
@@ -331,6 +542,42 @@ impl GameDatabase {
         }
     }
 
+    pub fn find_recipes_by_input(&self, item: &Item) -> Vec<Rc<Recipe>> {
+        self.recipes
+            .iter()
+            .filter(|r| r.has_input_item(item))
+            .cloned()
+            .collect()
+    }
+
+    /// Every item that can be crafted from the given resources using only the given
+    /// recipes, including the resources themselves.  A recipe is usable once all of its
+    /// inputs are reachable.
+    pub fn reachable_items(
+        &self,
+        from_resources: &[Rc<Item>],
+        enabled: &[Rc<Recipe>],
+    ) -> HashSet<Rc<Item>> {
+        let mut reachable: HashSet<Rc<Item>> = from_resources.iter().cloned().collect();
+        let mut pending: Vec<&Rc<Recipe>> = enabled.iter().collect();
+
+        loop {
+            let (usable, unusable): (Vec<_>, Vec<_>) = pending
+                .into_iter()
+                .partition(|recipe| recipe.inputs.iter().all(|i| reachable.contains(&i.item)));
+            if usable.is_empty() {
+                break;
+            }
+
+            for recipe in usable {
+                reachable.extend(recipe.outputs.iter().map(|o| Rc::clone(&o.item)));
+            }
+            pending = unusable;
+        }
+
+        reachable
+    }
+
     #[inline]
     pub fn find_item_producers(&self, item: &Item) -> Vec<Rc<Building>> {
         self.buildings
@@ -340,12 +587,38 @@ impl GameDatabase {
             .collect()
     }
 
+    #[inline]
+    pub fn find_power_generators(&self) -> Vec<Rc<Building>> {
+        self.buildings
+            .iter()
+            .filter(|b| b.is_power_generator())
+            .cloned()
+            .collect()
+    }
+
     #[inline]
     pub fn get_resource_limit(&self, item: &Rc<Item>) -> FloatType {
         self.resource_limits.get(item).copied().unwrap_or(0.0)
     }
 }
 
+fn unique_names<I: Iterator<Item = String>>(names: I) -> Vec<String> {
+    let mut unique_names = Vec::new();
+    for name in names {
+        if !unique_names.contains(&name) {
+            unique_names.push(name);
+        }
+    }
+    unique_names
+}
+
+fn total_sink_points(values: &[ItemPerMinute]) -> FloatType {
+    values
+        .iter()
+        .map(|v| v.item.sink_points as FloatType * v.amount)
+        .sum()
+}
+
 #[cfg(test)]
 pub mod test {
     use std::path::PathBuf;
@@ -367,3 +640,192 @@ pub mod test {
         get_test_game_db().filter(|r| !r.alternate || recipe_keys.contains(&r.key.as_str()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::{test::get_test_game_db, GameDatabase, GameDatabaseError};
+
+    const MOD_GAME_DB: &str = r#"{
+        "recipes": [
+            {
+                "key": "Recipe_IngotIron_C",
+                "name": "Iron Ingot",
+                "inputs": [{"item": "Desc_OreIron_C", "amount": 1}],
+                "outputs": [{"item": "Desc_IronIngot_C", "amount": 2}],
+                "craft_time_secs": 2,
+                "building": "Desc_SmelterMk1_C"
+            },
+            {
+                "key": "Recipe_Modded_IronPlate_C",
+                "name": "Modded Iron Plate",
+                "inputs": [{"item": "Desc_OreIron_C", "amount": 1}],
+                "outputs": [{"item": "Desc_IronPlate_C", "amount": 1}],
+                "craft_time_secs": 2,
+                "building": "Desc_ConstructorMk1_C"
+            }
+        ],
+        "resource_limits": {
+            "Desc_OreIron_C": 100
+        }
+    }"#;
+
+    fn write_temp_file(name: &str, contents: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "satisfactory-planner-{}-{}",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn test_game_db_path() -> PathBuf {
+        let mut game_db_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        game_db_path.push("game-db.json");
+        game_db_path
+    }
+
+    #[test]
+    fn from_files_merges_later_files_over_earlier() {
+        let mod_path = write_temp_file("mod.json", MOD_GAME_DB);
+        let game_db = GameDatabase::from_files(&[test_game_db_path(), mod_path.clone()]).unwrap();
+        fs::remove_file(mod_path).unwrap();
+
+        let base_game_db = get_test_game_db();
+        assert_eq!(game_db.recipes.len(), base_game_db.recipes.len() + 1);
+        assert_eq!(game_db.items.len(), base_game_db.items.len());
+
+        let iron_ingot = game_db.find_recipe("Recipe_IngotIron_C").unwrap();
+        assert_eq!(iron_ingot.outputs[0].amount, 60.0);
+        assert!(game_db.find_recipe("Recipe_Modded_IronPlate_C").is_some());
+
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        assert_eq!(game_db.get_resource_limit(&iron_ore), 100.0);
+    }
+
+    #[test]
+    fn from_files_rejects_duplicates_within_a_file() {
+        let recipe = r#"{
+            "key": "Recipe_IngotIron_C",
+            "name": "Iron Ingot",
+            "inputs": [{"item": "Desc_OreIron_C", "amount": 1}],
+            "outputs": [{"item": "Desc_IronIngot_C", "amount": 1}],
+            "craft_time_secs": 2,
+            "building": "Desc_SmelterMk1_C"
+        }"#;
+        let mod_path = write_temp_file(
+            "duplicate.json",
+            &format!(r#"{{"recipes": [{}, {}]}}"#, recipe, recipe),
+        );
+        let result = GameDatabase::from_files(&[test_game_db_path(), mod_path.clone()]);
+        fs::remove_file(mod_path).unwrap();
+
+        assert_eq!(
+            result.unwrap_err().downcast_ref::<GameDatabaseError>(),
+            Some(&GameDatabaseError::DuplicateRecipeKey(
+                "Recipe_IngotIron_C".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn from_file_checked_warnings() {
+        let (_, warnings) = GameDatabase::from_file_checked(test_game_db_path()).unwrap();
+
+        assert!(warnings.contains(
+            &"Recipe `Recipe_Alternate_AutomatedMiner_C`: Inputs are worth 1644 sink points but outputs are only worth 56".to_string()
+        ));
+        assert!(warnings.contains(
+            &"Recipe `Recipe_Biomass_Leaves_C`: No recipe or building can supply the input `Desc_Leaves_C`".to_string()
+        ));
+        assert!(!warnings
+            .iter()
+            .any(|w| w.contains("Recipe_IngotIron_C") || w.contains("Recipe_UnpackageFuel_C")));
+    }
+
+    #[test]
+    fn find_recipes_by_input_iron_ore() {
+        let game_db = get_test_game_db();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+
+        let mut recipe_keys: Vec<String> = game_db
+            .find_recipes_by_input(&iron_ore)
+            .iter()
+            .map(|r| r.key.clone())
+            .collect();
+        recipe_keys.sort();
+
+        assert_eq!(
+            recipe_keys,
+            vec![
+                "Recipe_Alternate_CokeSteelIngot_C",
+                "Recipe_Alternate_CopperAlloyIngot_C",
+                "Recipe_Alternate_IngotIron_C",
+                "Recipe_Alternate_IngotSteel_2_C",
+                "Recipe_Alternate_PureIronIngot_C",
+                "Recipe_IngotIron_C",
+                "Recipe_IngotSteel_C",
+            ]
+        );
+    }
+
+    #[test]
+    fn find_recipes_by_input_water() {
+        let game_db = get_test_game_db();
+        let water = game_db.find_item("Desc_Water_C").unwrap();
+
+        let recipes = game_db.find_recipes_by_input(&water);
+
+        assert_eq!(recipes.len(), 18);
+        assert!(recipes.iter().all(|r| r.has_input_item(&water)));
+        assert!(recipes.iter().any(|r| r.key == "Recipe_AluminaSolution_C"));
+        assert!(!recipes.iter().any(|r| r.key == "Recipe_UnpackageWater_C"));
+    }
+
+    #[test]
+    fn suggest_items_and_recipes() {
+        let game_db = get_test_game_db();
+
+        assert!(game_db
+            .suggest_items("Iron Plat")
+            .contains(&"Iron Plate".to_string()));
+        assert_eq!(game_db.suggest_items("Desc_IronPlat_C"), vec!["Iron Plate"]);
+        assert!(game_db.suggest_items("Not An Item At All").is_empty());
+        assert!(game_db
+            .suggest_recipes("Iron Ingott")
+            .contains(&"Iron Ingot".to_string()));
+    }
+
+    #[test]
+    fn reachable_items_from_iron_ore() {
+        let game_db = get_test_game_db();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+
+        let reachable = game_db.reachable_items(&[iron_ore], &game_db.recipes);
+
+        for key in [
+            "Desc_OreIron_C",
+            "Desc_IronIngot_C",
+            "Desc_IronPlate_C",
+            "Desc_IronRod_C",
+            "Desc_IronScrew_C",
+            "Desc_IronPlateReinforced_C",
+        ] {
+            assert!(
+                reachable.contains(&game_db.find_item(key).unwrap()),
+                "{} should be reachable",
+                key
+            );
+        }
+        for key in ["Desc_CopperIngot_C", "Desc_Cement_C", "Desc_SteelIngot_C"] {
+            assert!(
+                !reachable.contains(&game_db.find_item(key).unwrap()),
+                "{} should not be reachable",
+                key
+            );
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use serde::Deserialize;
+use std::{collections::HashMap, rc::Rc};
+
+use super::{GameDatabase, GameDatabaseError, Item};
+use crate::utils::FloatType;
+
+const IMPURE_MULTIPLIER: FloatType = 0.5;
+const NORMAL_MULTIPLIER: FloatType = 1.0;
+const PURE_MULTIPLIER: FloatType = 2.0;
+
+#[derive(Debug, Copy, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct NodeCounts {
+    pub impure: u32,
+    pub normal: u32,
+    pub pure: u32,
+}
+
+/// Describes the resource nodes available to a factory and the miners placed on them,
+/// e.g.
+///
+/// ```yaml
+/// mk: 2
+/// clock_speed: 150
+/// nodes:
+///   Iron Ore: { impure: 0, normal: 3, pure: 2 }
+/// ```
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ResourcePreset {
+    #[serde(default = "default_mk")]
+    pub mk: u8,
+    #[serde(default = "default_clock_speed")]
+    pub clock_speed: FloatType,
+    pub nodes: HashMap<String, NodeCounts>,
+}
+
+fn default_mk() -> u8 {
+    1
+}
+
+fn default_clock_speed() -> FloatType {
+    100.0
+}
+
+/// Items per minute extracted by a single miner of the given tier on a normal node
+pub fn miner_rate(mk: u8) -> Option<FloatType> {
+    match mk {
+        1 => Some(60.0),
+        2 => Some(120.0),
+        3 => Some(240.0),
+        _ => None,
+    }
+}
+
+impl NodeCounts {
+    /// The number of normal nodes these nodes are equivalent to
+    pub fn normal_equivalent(&self) -> FloatType {
+        self.impure as FloatType * IMPURE_MULTIPLIER
+            + self.normal as FloatType * NORMAL_MULTIPLIER
+            + self.pure as FloatType * PURE_MULTIPLIER
+    }
+}
+
+impl ResourcePreset {
+    pub fn resource_limits(
+        &self,
+        game_db: &GameDatabase,
+    ) -> Result<HashMap<Rc<Item>, FloatType>, GameDatabaseError> {
+        let rate = miner_rate(self.mk).ok_or(GameDatabaseError::UnknownMinerTier(self.mk))?
+            * self.clock_speed
+            / 100.0;
+
+        let mut resource_limits = HashMap::new();
+        for (item_name, nodes) in &self.nodes {
+            let item = game_db
+                .find_item(item_name)
+                .ok_or_else(|| GameDatabaseError::UnknownItemKey(item_name.clone()))?;
+            if !item.resource {
+                return Err(GameDatabaseError::ItemNotAResource(item.key.clone()));
+            }
+
+            resource_limits.insert(item, nodes.normal_equivalent() * rate);
+        }
+
+        Ok(resource_limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test::get_test_game_db;
+
+    #[test]
+    fn resource_limits_from_preset() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            mk: 2
+            nodes:
+              Iron Ore: { impure: 1, normal: 2, pure: 1 }
+              Desc_OreCopper_C: { normal: 1 }
+        #";
+        let preset: ResourcePreset = serde_yaml::from_str(yaml).unwrap();
+        let resource_limits = preset.resource_limits(&game_db).unwrap();
+
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let copper_ore = game_db.find_item("Desc_OreCopper_C").unwrap();
+        assert_eq!(resource_limits.len(), 2);
+        assert_eq!(resource_limits[&iron_ore], 540.0);
+        assert_eq!(resource_limits[&copper_ore], 120.0);
+    }
+
+    #[test]
+    fn resource_limits_from_preset_overclocked() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            mk: 3
+            clock_speed: 250
+            nodes:
+              Iron Ore: { normal: 1 }
+        #";
+        let preset: ResourcePreset = serde_yaml::from_str(yaml).unwrap();
+        let resource_limits = preset.resource_limits(&game_db).unwrap();
+
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        assert_eq!(resource_limits[&iron_ore], 600.0);
+    }
+
+    #[test]
+    fn resource_limits_from_preset_unknown_mk() {
+        let game_db = get_test_game_db();
+
+        let preset = ResourcePreset {
+            mk: 4,
+            clock_speed: 100.0,
+            nodes: HashMap::new(),
+        };
+
+        assert_eq!(
+            preset.resource_limits(&game_db).unwrap_err(),
+            GameDatabaseError::UnknownMinerTier(4)
+        );
+    }
+}
@@ -24,11 +24,20 @@ pub(super) struct RecipeDefinition {
     pub craft_time_secs: FloatType,
     #[serde(default)]
     pub events: Vec<String>,
-    pub building: String,
     #[serde(default)]
+    pub tags: Vec<String>,
+    pub building: String,
+    #[serde(default, alias = "power_consumption")]
     pub power: RecipePower,
 }
 
+/// An amount of an item consumed or produced by a single craft of a recipe
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemPerCraft {
+    pub item: Rc<Item>,
+    pub amount: FloatType,
+}
+
 #[derive(Clone)]
 pub struct Recipe {
     pub key: String,
@@ -36,8 +45,11 @@ pub struct Recipe {
     pub alternate: bool,
     pub outputs: Vec<ItemPerMinute>,
     pub inputs: Vec<ItemPerMinute>,
+    pub outputs_per_craft: Vec<ItemPerCraft>,
+    pub inputs_per_craft: Vec<ItemPerCraft>,
     pub craft_time_secs: FloatType,
     pub events: Vec<String>,
+    pub tags: Vec<String>,
     pub building: Rc<Building>,
     pub power: RecipePower,
 }
@@ -51,6 +63,29 @@ impl Recipe {
             .average_mw_overclocked(self, clock_speed)
     }
 
+    /// The items per minute of `item` consumed by `building_count` buildings, computed
+    /// from the per craft amount so that it doesn't drift from the per minute rate
+    pub fn input_rate(&self, item: &Item, building_count: FloatType) -> Option<FloatType> {
+        self.inputs_per_craft
+            .iter()
+            .find(|i| *i.item == *item)
+            .map(|i| self.rate_per_min(i.amount, building_count))
+    }
+
+    /// The items per minute of `item` produced by `building_count` buildings, computed
+    /// from the per craft amount so that it doesn't drift from the per minute rate
+    pub fn output_rate(&self, item: &Item, building_count: FloatType) -> Option<FloatType> {
+        self.outputs_per_craft
+            .iter()
+            .find(|o| *o.item == *item)
+            .map(|o| self.rate_per_min(o.amount, building_count))
+    }
+
+    #[inline]
+    fn rate_per_min(&self, amount_per_craft: FloatType, building_count: FloatType) -> FloatType {
+        amount_per_craft * building_count * 60.0 / self.craft_time_secs
+    }
+
     #[inline]
     pub fn find_input_by_item(&self, item: &Item) -> Option<&ItemPerMinute> {
         self.inputs.iter().find(|output| *output.item == *item)
@@ -73,6 +108,11 @@ impl Recipe {
             .unwrap_or(false)
     }
 
+    #[inline]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
     #[inline]
     pub fn has_input_item(&self, item: &Item) -> bool {
         self.inputs.iter().any(|input| *input.item == *item)
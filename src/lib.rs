@@ -0,0 +1,4 @@
+pub mod game;
+pub mod listing;
+pub mod plan;
+pub mod utils;
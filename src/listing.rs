@@ -0,0 +1,645 @@
+use petgraph::stable_graph::NodeIndex;
+use serde::Serialize;
+use std::{collections::BTreeMap, rc::Rc};
+
+use crate::{
+    game::{GameDatabase, Item, ItemPerMinute, ItemState, Recipe},
+    plan::{
+        edge_logistics, is_reachable, path_between, AlternateImpact, FullPlanGraph, NodeWeight,
+        PlanConfig, PlanDiff, PlanNodeWeight, PruneReason, RecipeEfficiency, SolvedGraph,
+        SolvedNodeWeight, TimeUnit,
+    },
+    utils::FloatType,
+};
+
+#[derive(Debug, Serialize)]
+pub struct ItemAmountListing<'a> {
+    pub item: &'a str,
+    pub amount: FloatType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecipeListing<'a> {
+    pub key: &'a str,
+    pub name: &'a str,
+    pub alternate: bool,
+    pub building: &'a str,
+    pub inputs: Vec<ItemAmountListing<'a>>,
+    pub outputs: Vec<ItemAmountListing<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_output: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ItemListing<'a> {
+    pub key: &'a str,
+    pub name: &'a str,
+    pub resource: bool,
+    pub state: ItemState,
+    pub sink_points: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProducerListing<'a> {
+    pub key: &'a str,
+    pub name: &'a str,
+    pub item: &'a str,
+    pub amount: FloatType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlanNodeListing<'a> {
+    pub id: usize,
+    #[serde(rename = "type")]
+    pub node_type: &'static str,
+    pub key: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlanEdgeListing<'a> {
+    pub from: usize,
+    pub to: usize,
+    pub item: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SolvedNodeListing<'a> {
+    pub id: usize,
+    #[serde(rename = "type")]
+    pub node_type: &'static str,
+    pub key: &'a str,
+    /// Items per minute, or the building count for production, producer and generator
+    /// nodes
+    pub amount: FloatType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SolvedEdgeListing<'a> {
+    pub from: usize,
+    pub to: usize,
+    pub item: &'a str,
+    pub amount: FloatType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SolvedGraphListing<'a> {
+    pub nodes: Vec<SolvedNodeListing<'a>>,
+    pub edges: Vec<SolvedEdgeListing<'a>>,
+}
+
+/// One plan's result in a batch, either its solved graph or why it failed
+#[derive(Debug, Serialize)]
+pub struct BatchResultListing<'a> {
+    pub plan: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graph: Option<SolvedGraphListing<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EdgeLogisticsListing<'a> {
+    pub from: usize,
+    pub to: usize,
+    pub item: &'a str,
+    pub amount: FloatType,
+    pub belt_count: u32,
+    pub belt_utilization: FloatType,
+    pub exceeds_single_belt: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PruneReasonListing<'a> {
+    pub recipe: &'a str,
+    pub missing_inputs: Vec<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FullPlanGraphListing<'a> {
+    pub nodes: Vec<PlanNodeListing<'a>>,
+    pub edges: Vec<PlanEdgeListing<'a>>,
+    pub pruned: Vec<PruneReasonListing<'a>>,
+    pub enabled_recipes: Vec<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AlternateImpactListing<'a> {
+    pub recipe_key: &'a str,
+    pub recipe_name: &'a str,
+    pub resource_savings: FloatType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecipeEfficiencyListing<'a> {
+    pub recipe_key: &'a str,
+    pub recipe_name: &'a str,
+    pub resource_score_per_item: FloatType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NodePathListing {
+    pub reachable: bool,
+    pub path: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlanTotalsListing {
+    pub buildings: FloatType,
+    pub inputs: FloatType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlanDiffListing<'a> {
+    pub before: PlanTotalsListing,
+    pub after: PlanTotalsListing,
+    pub building_deltas: &'a BTreeMap<String, FloatType>,
+    pub input_deltas: BTreeMap<&'a str, FloatType>,
+}
+
+impl<'a> From<&'a ItemPerMinute> for ItemAmountListing<'a> {
+    fn from(value: &'a ItemPerMinute) -> Self {
+        Self {
+            item: &value.item.key,
+            amount: value.amount,
+        }
+    }
+}
+
+impl<'a> From<&'a Recipe> for RecipeListing<'a> {
+    fn from(recipe: &'a Recipe) -> Self {
+        Self {
+            key: &recipe.key,
+            name: &recipe.name,
+            alternate: recipe.alternate,
+            building: recipe.building.key(),
+            inputs: recipe.inputs.iter().map(ItemAmountListing::from).collect(),
+            outputs: recipe.outputs.iter().map(ItemAmountListing::from).collect(),
+            primary_output: None,
+        }
+    }
+}
+
+impl<'a> From<&'a Item> for ItemListing<'a> {
+    fn from(item: &'a Item) -> Self {
+        Self {
+            key: &item.key,
+            name: &item.name,
+            resource: item.resource,
+            state: item.state,
+            sink_points: item.sink_points,
+        }
+    }
+}
+
+pub fn list_items(game_db: &GameDatabase, resource: Option<bool>) -> Vec<ItemListing<'_>> {
+    game_db
+        .items
+        .iter()
+        .filter(|i| resource.map(|r| i.resource == r).unwrap_or(true))
+        .map(|i| ItemListing::from(i.as_ref()))
+        .collect()
+}
+
+/// Lists every item the game database's recipes can craft from the given resources,
+/// including the resources themselves, sorted by key
+pub fn list_reachable_items<'a>(
+    game_db: &'a GameDatabase,
+    resources: &[Rc<Item>],
+) -> Vec<ItemListing<'a>> {
+    let reachable = game_db.reachable_items(resources, &game_db.recipes);
+    let mut items: Vec<ItemListing> = game_db
+        .items
+        .iter()
+        .filter(|i| reachable.contains(*i))
+        .map(|i| ItemListing::from(i.as_ref()))
+        .collect();
+    items.sort_by(|a, b| a.key.cmp(b.key));
+    items
+}
+
+/// Lists the buildings that produce an item without any inputs, along with the item and
+/// how much of it one building produces per minute
+pub fn list_producers(game_db: &GameDatabase) -> Vec<ProducerListing<'_>> {
+    game_db
+        .buildings
+        .iter()
+        .filter(|b| b.is_item_producer())
+        .map(|b| {
+            let producer = b.as_item_producer();
+            ProducerListing {
+                key: &producer.key,
+                name: &producer.name,
+                item: &producer.output.item.key,
+                amount: producer.output.amount,
+            }
+        })
+        .collect()
+}
+
+/// Lists the recipes in the game database.  When `produces` is given only recipes with
+/// that item as an output are listed, along with whether it is their primary output.
+pub fn list_recipes<'a>(
+    game_db: &'a GameDatabase,
+    alternate: Option<bool>,
+    produces: Option<&Item>,
+) -> Vec<RecipeListing<'a>> {
+    game_db
+        .recipes
+        .iter()
+        .filter(|r| alternate.map(|a| r.alternate == a).unwrap_or(true))
+        .filter(|r| produces.map(|i| r.has_output_item(i)).unwrap_or(true))
+        .map(|r| RecipeListing {
+            primary_output: produces.map(|i| r.is_primary_output(i)),
+            ..RecipeListing::from(r.as_ref())
+        })
+        .collect()
+}
+
+pub fn list_full_plan_graph<'a>(
+    graph: &'a FullPlanGraph,
+    prune_reasons: &'a [PruneReason],
+    config: &'a PlanConfig,
+) -> FullPlanGraphListing<'a> {
+    let nodes = graph
+        .node_indices()
+        .map(|i| {
+            let (node_type, key) = match &graph[i] {
+                PlanNodeWeight::Input(item) => ("input", item.key.as_str()),
+                PlanNodeWeight::Output(item) => ("output", item.key.as_str()),
+                PlanNodeWeight::ByProduct(item) => ("by_product", item.key.as_str()),
+                PlanNodeWeight::Production(recipe, ..) => ("production", recipe.key.as_str()),
+                PlanNodeWeight::Producer(building) => ("producer", building.key()),
+                PlanNodeWeight::Generator(building, ..) => ("generator", building.key()),
+            };
+            PlanNodeListing {
+                id: i.index(),
+                node_type,
+                key,
+            }
+        })
+        .collect();
+
+    let edges = graph
+        .edge_indices()
+        .map(|e| {
+            let (from, to) = graph.edge_endpoints(e).unwrap();
+            PlanEdgeListing {
+                from: from.index(),
+                to: to.index(),
+                item: &graph[e].key,
+            }
+        })
+        .collect();
+
+    let pruned = prune_reasons
+        .iter()
+        .map(|reason| PruneReasonListing {
+            recipe: &reason.recipe.key,
+            missing_inputs: reason
+                .missing_inputs
+                .iter()
+                .map(|item| item.key.as_str())
+                .collect(),
+        })
+        .collect();
+
+    FullPlanGraphListing {
+        nodes,
+        edges,
+        pruned,
+        enabled_recipes: config.enabled_recipe_keys(),
+    }
+}
+
+/// Lists whether the items flowing out of `from` end up at `to`, and if so the shortest
+/// chain of node ids carrying them there
+pub fn list_node_path(graph: &SolvedGraph, from: NodeIndex, to: NodeIndex) -> NodePathListing {
+    let reachable = is_reachable(graph, from, to);
+    let path = if reachable {
+        path_between(graph, from, to).map(|path| path.iter().map(|&i| graph[i].id()).collect())
+    } else {
+        None
+    };
+    NodePathListing { reachable, path }
+}
+
+/// Lists every edge of a solved plan along with the belts, or pipelines for fluids, of
+/// the plan's configured tiers needed to carry it
+pub fn list_edge_logistics<'a>(
+    graph: &'a SolvedGraph,
+    config: &PlanConfig,
+) -> Vec<EdgeLogisticsListing<'a>> {
+    let logistics = edge_logistics(graph, config.belt_tier, config.pipe_tier);
+    graph
+        .edge_indices()
+        .map(|e| {
+            let (from, to) = graph.edge_endpoints(e).unwrap();
+            EdgeLogisticsListing {
+                from: from.index(),
+                to: to.index(),
+                item: &graph[e].item.key,
+                amount: config.time_unit.rate_in_unit(graph[e].amount),
+                belt_count: logistics[&e].belt_count,
+                belt_utilization: logistics[&e].belt_utilization,
+                exceeds_single_belt: logistics[&e].exceeds_single_belt,
+            }
+        })
+        .collect()
+}
+
+/// Lists a solved graph with item amounts given per `time_unit` and building counts as is
+pub fn list_solved_graph(graph: &SolvedGraph, time_unit: TimeUnit) -> SolvedGraphListing<'_> {
+    let nodes = graph
+        .node_indices()
+        .map(|i| {
+            let (node_type, key, amount) = match &graph[i] {
+                SolvedNodeWeight::Input(input) => (
+                    "input",
+                    input.item.key.as_str(),
+                    time_unit.rate_in_unit(input.amount),
+                ),
+                SolvedNodeWeight::Output(output) => (
+                    "output",
+                    output.item.key.as_str(),
+                    time_unit.rate_in_unit(output.amount),
+                ),
+                SolvedNodeWeight::ByProduct(by_product) => (
+                    "by_product",
+                    by_product.item.key.as_str(),
+                    time_unit.rate_in_unit(by_product.amount),
+                ),
+                SolvedNodeWeight::Production {
+                    recipe,
+                    building_count,
+                    ..
+                } => ("production", recipe.key.as_str(), *building_count),
+                SolvedNodeWeight::Producer(building, building_count) => {
+                    ("producer", building.key(), *building_count)
+                }
+                SolvedNodeWeight::Generator(building, _, building_count) => {
+                    ("generator", building.key(), *building_count)
+                }
+            };
+            SolvedNodeListing {
+                id: i.index(),
+                node_type,
+                key,
+                amount,
+            }
+        })
+        .collect();
+
+    let edges = graph
+        .edge_indices()
+        .map(|e| {
+            let (from, to) = graph.edge_endpoints(e).unwrap();
+            SolvedEdgeListing {
+                from: from.index(),
+                to: to.index(),
+                item: &graph[e].item.key,
+                amount: time_unit.rate_in_unit(graph[e].amount),
+            }
+        })
+        .collect();
+
+    SolvedGraphListing { nodes, edges }
+}
+
+/// Lists a plan diff with the input totals and deltas given per `time_unit`
+pub fn list_plan_diff(diff: &PlanDiff, time_unit: TimeUnit) -> PlanDiffListing<'_> {
+    PlanDiffListing {
+        before: PlanTotalsListing {
+            buildings: diff.before_total_buildings,
+            inputs: time_unit.rate_in_unit(diff.before_total_inputs),
+        },
+        after: PlanTotalsListing {
+            buildings: diff.after_total_buildings,
+            inputs: time_unit.rate_in_unit(diff.after_total_inputs),
+        },
+        building_deltas: &diff.building_deltas,
+        input_deltas: diff
+            .input_deltas
+            .iter()
+            .map(|(key, delta)| (key.as_str(), time_unit.rate_in_unit(*delta)))
+            .collect(),
+    }
+}
+
+pub fn list_alternate_impacts(impacts: &[AlternateImpact]) -> Vec<AlternateImpactListing<'_>> {
+    impacts
+        .iter()
+        .map(|impact| AlternateImpactListing {
+            recipe_key: &impact.recipe.key,
+            recipe_name: &impact.recipe.name,
+            resource_savings: impact.resource_savings,
+        })
+        .collect()
+}
+
+pub fn list_recipe_efficiencies(
+    efficiencies: &[RecipeEfficiency],
+) -> Vec<RecipeEfficiencyListing<'_>> {
+    efficiencies
+        .iter()
+        .map(|efficiency| RecipeEfficiencyListing {
+            recipe_key: &efficiency.recipe.key,
+            recipe_name: &efficiency.recipe.name,
+            resource_score_per_item: efficiency.resource_score_per_item,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::test::{get_test_game_db, get_test_game_db_with_recipes},
+        plan::{build_full_plan_with_prune_reasons, solve, PlanConfig},
+    };
+
+    #[test]
+    fn list_recipes_filters_alternates() {
+        let game_db = get_test_game_db();
+
+        let all = list_recipes(&game_db, None, None);
+        let alternates = list_recipes(&game_db, Some(true), None);
+        let base = list_recipes(&game_db, Some(false), None);
+
+        assert_eq!(all.len(), game_db.recipes.len());
+        assert_eq!(alternates.len() + base.len(), all.len());
+        assert!(alternates.iter().all(|r| r.alternate));
+        assert!(base.iter().all(|r| !r.alternate));
+        assert!(alternates
+            .iter()
+            .any(|r| r.key == "Recipe_Alternate_PureIronIngot_C"));
+    }
+
+    #[test]
+    fn list_recipes_by_produced_item() {
+        let game_db = get_test_game_db();
+        let polymer_resin = game_db.find_item("Desc_PolymerResin_C").unwrap();
+
+        let recipes = list_recipes(&game_db, None, Some(&polymer_resin));
+
+        let mut keys: Vec<(&str, Option<bool>)> =
+            recipes.iter().map(|r| (r.key, r.primary_output)).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                ("Recipe_Alternate_HeavyOilResidue_C", Some(false)),
+                ("Recipe_Alternate_PolymerResin_C", Some(true)),
+                ("Recipe_LiquidFuel_C", Some(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn list_items_filters_resources() {
+        let game_db = get_test_game_db();
+
+        let resources = list_items(&game_db, Some(true));
+
+        assert_eq!(resources.len(), game_db.resource_limits.len());
+        assert!(resources.iter().all(|i| i.resource));
+        assert!(resources.iter().any(|i| i.key == "Desc_OreIron_C"));
+        assert!(!resources.iter().any(|i| i.key == "Desc_IronIngot_C"));
+    }
+
+    #[test]
+    fn list_reachable_items_from_copper_ore() {
+        let game_db = get_test_game_db();
+        let copper_ore = game_db.find_item("Desc_OreCopper_C").unwrap();
+
+        let items = list_reachable_items(&game_db, &[copper_ore]);
+
+        let keys: Vec<&str> = items.iter().map(|i| i.key).collect();
+        assert!(keys.contains(&"Desc_OreCopper_C"));
+        assert!(keys.contains(&"Desc_Wire_C"));
+        assert!(!keys.contains(&"Desc_IronIngot_C"));
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn list_producers_gift_tree() {
+        let game_db = get_test_game_db();
+
+        let producers = list_producers(&game_db);
+
+        let gift_tree = producers
+            .iter()
+            .find(|p| p.key == "Desc_TreeGiftProducer_C")
+            .unwrap();
+        assert_eq!(gift_tree.item, "Desc_Gift_C");
+        assert_eq!(gift_tree.amount, 15.0);
+    }
+
+    #[test]
+    fn list_solved_graph_iron_plate() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let listing = list_solved_graph(&graph, config.time_unit);
+
+        let mut nodes: Vec<(&str, &str, FloatType)> = listing
+            .nodes
+            .iter()
+            .map(|n| (n.node_type, n.key, n.amount))
+            .collect();
+        nodes.sort_by(|a, b| a.0.cmp(b.0));
+        assert_eq!(
+            nodes,
+            vec![
+                ("input", "Desc_OreIron_C", 60.0),
+                ("output", "Desc_IronPlate_C", 40.0),
+                ("production", "Recipe_IngotIron_C", 2.0),
+                ("production", "Recipe_IronPlate_C", 2.0),
+            ]
+        );
+        assert_eq!(listing.edges.len(), 3);
+    }
+
+    #[test]
+    fn solved_edge_json_uses_item_keys() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 30.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let json = serde_json::to_value(list_solved_graph(&graph, config.time_unit)).unwrap();
+        let mut edge_items: Vec<&str> = json["edges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["item"].as_str().unwrap())
+            .collect();
+        edge_items.sort();
+        assert_eq!(edge_items, vec!["Desc_IronIngot_C", "Desc_OreIron_C"]);
+    }
+
+    #[test]
+    fn list_node_path_from_ore_to_plate() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        let graph = solve(&config).unwrap();
+        let find = |id: &str| graph.node_indices().find(|&i| graph[i].id() == id).unwrap();
+        let ore = find("input:Desc_OreIron_C");
+        let plate = find("output:Desc_IronPlate_C");
+
+        let listing = list_node_path(&graph, ore, plate);
+        assert!(listing.reachable);
+        assert_eq!(
+            listing.path.unwrap(),
+            vec![
+                "input:Desc_OreIron_C",
+                "prod:Recipe_IngotIron_C",
+                "prod:Recipe_IronPlate_C",
+                "output:Desc_IronPlate_C",
+            ]
+        );
+
+        let listing = list_node_path(&graph, plate, ore);
+        assert!(!listing.reachable);
+        assert_eq!(listing.path, None);
+    }
+
+    #[test]
+    fn list_full_plan_graph_uses_keys() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        let (graph, prune_reasons) = build_full_plan_with_prune_reasons(&config).unwrap();
+
+        let listing = list_full_plan_graph(&graph, &prune_reasons, &config);
+
+        let mut nodes: Vec<(&str, &str)> =
+            listing.nodes.iter().map(|n| (n.node_type, n.key)).collect();
+        nodes.sort();
+        assert_eq!(
+            nodes,
+            vec![
+                ("by_product", "Desc_IronIngot_C"),
+                ("by_product", "Desc_IronPlate_C"),
+                ("input", "Desc_OreIron_C"),
+                ("output", "Desc_IronPlate_C"),
+                ("production", "Recipe_IngotIron_C"),
+                ("production", "Recipe_IronPlate_C"),
+            ]
+        );
+
+        let ids: Vec<usize> = listing.nodes.iter().map(|n| n.id).collect();
+        assert_eq!(listing.edges.len(), graph.edge_count());
+        assert!(listing
+            .edges
+            .iter()
+            .all(|e| ids.contains(&e.from) && ids.contains(&e.to)));
+        assert!(listing.edges.iter().any(|e| e.item == "Desc_OreIron_C"));
+        assert_eq!(
+            listing.enabled_recipes,
+            vec!["Recipe_IngotIron_C", "Recipe_IronPlate_C"]
+        );
+    }
+}
@@ -1,85 +1,546 @@
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
-use crate::{
+use clap::{Parser, Subcommand, ValueEnum};
+use petgraph::stable_graph::NodeIndex;
+use satisfactory_planner::{
     game::GameDatabase,
-    plan::{print_graph, solve, PlanConfig},
+    listing,
+    plan::{
+        alternate_impacts, build_full_plan, build_full_plan_with_prune_reasons, compare_with_csv,
+        diff_plans, extractor_counts, print_graph, recipe_efficiencies, resource_costs,
+        solve_with_cache, solve_with_objective, solved_graph_to_csv, solved_graph_to_dot,
+        FullPlanCache, NodeWeight, PlanConfig, PlanSummary, RequestLimits, SolvedGraph,
+        SolvedNodeWeight,
+    },
     utils::round,
 };
-use clap::Parser;
-use plan::SolvedNodeWeight;
-
-mod game;
-mod plan;
-mod utils;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 struct Args {
-    /// Path to the game database json.  Defaults to game-db.json
-    #[arg(short = 'd', long = "game-db")]
-    game_db: Option<PathBuf>,
+    /// Path to the game database json.  May be repeated to merge mod content over the
+    /// base game, with later files taking precedence.  Defaults to game-db.json
+    #[arg(short = 'd', long = "game-db", global = true)]
+    game_db: Vec<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 
     /// Path to the plan configuration yaml
-    #[arg()]
-    plan: PathBuf,
+    #[arg(required = true)]
+    plan: Option<PathBuf>,
 
     /// Print out the intermediary full plan graph instead
     #[arg(short = 'f', long = "full-plan-graph")]
     full_plan_graph: bool,
+
+    /// Print how long building the plan graph and solving it took after the summary.
+    /// Nothing else reports solve times, and they aren't printed with the csv or dot
+    /// formats.  Also warns when the tie-break between equally optimal plans fails
+    #[arg(long)]
+    debug: bool,
+
+    /// How to print the solved plan
+    #[arg(long, value_enum, default_value_t = OutputFormat::Summary)]
+    format: OutputFormat,
+
+    /// Reject plans with more than this many outputs
+    #[arg(long, global = true)]
+    max_outputs: Option<usize>,
+
+    /// Reject plans enabling more than this many recipes
+    #[arg(long, global = true)]
+    max_recipes: Option<usize>,
+
+    /// Give up solving a plan once this many seconds have passed.  The limit is checked
+    /// between solver steps, a single linear program solve can't be interrupted
+    #[arg(long, global = true)]
+    solve_timeout_secs: Option<u64>,
+}
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq)]
+enum OutputFormat {
+    /// The solved graph followed by a summary of the plan
+    Summary,
+    /// The same csv as the csv command
+    Csv,
+    /// The same graphviz dot as the dot command
+    Dot,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the recipes in the game database as json
+    Recipes {
+        /// Only include alternate recipes when true, or only base recipes when false
+        #[arg(long)]
+        alternate: Option<bool>,
+        /// Only include recipes producing the item with this name or key
+        #[arg(long)]
+        produces: Option<String>,
+    },
+    /// Print the items in the game database as json
+    Items {
+        /// Only include resources when true, or only non-resources when false
+        #[arg(long)]
+        resource: Option<bool>,
+    },
+    /// Print every item the game database's recipes can craft from just the given
+    /// resources as json
+    Reachable {
+        /// Names or keys of the resources to start from
+        #[arg(required = true)]
+        resources: Vec<String>,
+    },
+    /// Print the buildings producing items without any inputs, e.g. FICSMAS Gift Trees, as
+    /// json
+    Producers,
+    /// Solve a plan and print only the solved graph in Graphviz DOT format
+    Dot {
+        /// Path to the plan configuration yaml
+        plan: PathBuf,
+    },
+    /// Solve a plan and print the buildings, inputs and outputs as csv
+    Csv {
+        /// Path to the plan configuration yaml
+        plan: PathBuf,
+    },
+    /// Solve a plan and compare it with a csv previously exported with the csv command,
+    /// warning about any building counts that changed
+    Import {
+        /// Path to the plan configuration yaml
+        plan: PathBuf,
+        /// Path to the previously exported csv
+        csv: PathBuf,
+    },
+    /// Solve several plans and print each one's solved graph, or why it failed, as a line
+    /// of json as soon as it is solved
+    Batch {
+        /// Paths to the plan configuration yamls
+        #[arg(required = true)]
+        plans: Vec<PathBuf>,
+    },
+    /// Rank the alternate recipes a plan doesn't enable by how much each would lower its
+    /// resource usage and print them as json
+    Alternates {
+        /// Path to the plan configuration yaml
+        plan: PathBuf,
+    },
+    /// Rank the plan's enabled recipes producing an item by the resources each needs per
+    /// item and print them as json.  The plan's outputs are ignored.
+    Efficiency {
+        /// Path to the plan configuration yaml
+        plan: PathBuf,
+        /// Name or key of the item to rank the recipes of
+        item: String,
+    },
+    /// Build a plan's full graph without solving it and print it as json
+    FullGraph {
+        /// Path to the plan configuration yaml
+        plan: PathBuf,
+    },
+    /// Solve a plan and print the belts and pipelines needed for each of its edges as json
+    Logistics {
+        /// Path to the plan configuration yaml
+        plan: PathBuf,
+    },
+    /// Solve two plans and print the differences in buildings and inputs between them as
+    /// json
+    Diff {
+        /// Path to the plan configuration yaml to compare against
+        before: PathBuf,
+        /// Path to the plan configuration yaml to compare
+        after: PathBuf,
+    },
+    /// Solve a plan and print whether the items leaving one node end up at another, and
+    /// the shortest chain of nodes carrying them there, as json
+    Path {
+        /// Path to the plan configuration yaml
+        plan: PathBuf,
+        /// Id of the node to start from, as in the dot output, e.g. input:Desc_OreIron_C
+        from: String,
+        /// Id of the node to end at, as in the dot output, e.g. output:Desc_Motor_C
+        to: String,
+    },
+    /// Check the game database for likely data errors
+    CheckDb,
+    /// Check that a plan configuration is valid without solving it
+    Validate {
+        /// Path to the plan configuration yaml
+        plan: PathBuf,
+    },
 }
 
 fn main() {
     let args = Args::parse();
 
-    let game_db_path = args.game_db.unwrap_or(PathBuf::from("game-db.json"));
+    let game_db_paths = if args.game_db.is_empty() {
+        vec![PathBuf::from("game-db.json")]
+    } else {
+        args.game_db
+    };
 
-    let game_db = GameDatabase::from_file(&game_db_path).unwrap_or_else(|e| {
-        panic!(
-            "Failed to load game database {}: {}",
-            game_db_path.display(),
-            e
-        );
+    let game_db = GameDatabase::from_files(&game_db_paths).unwrap_or_else(|e| {
+        panic!("Failed to load game database: {}", e);
+    });
+    let limits = RequestLimits {
+        max_outputs: args.max_outputs,
+        max_recipes: args.max_recipes,
+        solve_timeout: args.solve_timeout_secs.map(Duration::from_secs),
+    };
+
+    // one cache for the whole command, so plans solved more than once, or sharing their
+    // recipes and outputs, only build their full plan graph once
+    let mut cache = FullPlanCache::default();
+
+    match args.command {
+        Some(Command::Recipes {
+            alternate,
+            produces,
+        }) => {
+            let produces = produces.map(|name| {
+                game_db
+                    .find_item(&name)
+                    .unwrap_or_else(|| panic!("No item exists with the name or key `{}`", name))
+            });
+            print_json(&listing::list_recipes(
+                &game_db,
+                alternate,
+                produces.as_deref(),
+            ));
+        }
+        Some(Command::Items { resource }) => {
+            print_json(&listing::list_items(&game_db, resource));
+        }
+        Some(Command::Reachable { resources }) => {
+            let resources: Vec<_> = resources
+                .iter()
+                .map(|name| {
+                    game_db
+                        .find_item(name)
+                        .unwrap_or_else(|| panic!("No item exists with the name or key `{}`", name))
+                })
+                .collect();
+            print_json(&listing::list_reachable_items(&game_db, &resources));
+        }
+        Some(Command::Producers) => {
+            print_json(&listing::list_producers(&game_db));
+        }
+        Some(Command::Dot { plan }) => {
+            let config = load_plan(&plan, &game_db, &limits);
+            let graph = solve_plan(&config, &mut cache);
+            println!("{}", solved_graph_to_dot(&graph, config.time_unit));
+        }
+        Some(Command::Csv { plan }) => {
+            let config = load_plan(&plan, &game_db, &limits);
+            let graph = solve_plan(&config, &mut cache);
+            print!("{}", solved_graph_to_csv(&graph, config.time_unit));
+        }
+        Some(Command::Import { plan, csv }) => {
+            import_plan(&plan, &csv, &game_db, &limits, &mut cache)
+        }
+        Some(Command::Batch { plans }) => solve_batch(&plans, &game_db, &limits, &mut cache),
+        Some(Command::Alternates { plan }) => {
+            let config = load_plan(&plan, &game_db, &limits);
+            let impacts = alternate_impacts(&config, &game_db, &mut cache).unwrap_or_else(|e| {
+                panic!("Failed to solve plan: {}", e);
+            });
+            print_json(&listing::list_alternate_impacts(&impacts));
+        }
+        Some(Command::Efficiency { plan, item }) => {
+            let config = load_plan(&plan, &game_db, &limits);
+            let item = config.game_db.find_item(&item).unwrap_or_else(|| {
+                panic!("No item exists with the name or key `{}`", item);
+            });
+            let efficiencies =
+                recipe_efficiencies(&config, &item, &mut cache).unwrap_or_else(|e| {
+                    panic!("Failed to solve plan: {}", e);
+                });
+            print_json(&listing::list_recipe_efficiencies(&efficiencies));
+        }
+        Some(Command::FullGraph { plan }) => {
+            let plan_config = load_plan(&plan, &game_db, &limits);
+            let (graph, prune_reasons) = build_full_plan_with_prune_reasons(&plan_config)
+                .unwrap_or_else(|e| {
+                    panic!("Failed to build full plan graph {}: {}", plan.display(), e);
+                });
+            print_json(&listing::list_full_plan_graph(
+                &graph,
+                &prune_reasons,
+                &plan_config,
+            ));
+        }
+        Some(Command::Logistics { plan }) => {
+            let plan_config = load_plan(&plan, &game_db, &limits);
+            let graph = solve_plan(&plan_config, &mut cache);
+            print_json(&listing::list_edge_logistics(&graph, &plan_config));
+        }
+        Some(Command::Diff { before, after }) => {
+            let before = solve_plan(&load_plan(&before, &game_db, &limits), &mut cache);
+            let after_config = load_plan(&after, &game_db, &limits);
+            let after = solve_plan(&after_config, &mut cache);
+            print_json(&listing::list_plan_diff(
+                &diff_plans(&before, &after),
+                after_config.time_unit,
+            ));
+        }
+        Some(Command::Path { plan, from, to }) => {
+            let graph = solve_plan(&load_plan(&plan, &game_db, &limits), &mut cache);
+            let from = find_node(&graph, &from);
+            let to = find_node(&graph, &to);
+            print_json(&listing::list_node_path(&graph, from, to));
+        }
+        Some(Command::Validate { plan }) => validate_plan(&plan, &game_db, &limits),
+        Some(Command::CheckDb) => {
+            let warnings = game_db.find_warnings();
+            for warning in &warnings {
+                println!("{}", warning);
+            }
+            println!("Found {} warnings", warnings.len());
+        }
+        None => create_plan(
+            &args.plan.unwrap(),
+            args.full_plan_graph,
+            args.debug,
+            args.format,
+            &game_db,
+            &limits,
+            &mut cache,
+        ),
+    }
+}
+
+fn print_json<T: serde::Serialize>(value: &T) {
+    let json = serde_json::to_string_pretty(value).unwrap_or_else(|e| {
+        panic!("Failed to serialize json: {}", e);
+    });
+    println!("{}", json);
+}
+
+fn solve_batch(
+    plan_paths: &[PathBuf],
+    game_db: &GameDatabase,
+    limits: &RequestLimits,
+    cache: &mut FullPlanCache,
+) {
+    for plan_path in plan_paths {
+        let result = PlanConfig::from_file(plan_path, game_db).and_then(|mut plan| {
+            plan.apply_limits(limits)?;
+            Ok((solve_with_cache(&plan, cache)?, plan.time_unit))
+        });
+
+        let listing = match &result {
+            Ok((graph, time_unit)) => listing::BatchResultListing {
+                plan: plan_path.display().to_string(),
+                graph: Some(listing::list_solved_graph(graph, *time_unit)),
+                error: None,
+            },
+            Err(e) => listing::BatchResultListing {
+                plan: plan_path.display().to_string(),
+                graph: None,
+                error: Some(e.to_string()),
+            },
+        };
+        let json = serde_json::to_string(&listing).unwrap_or_else(|e| {
+            panic!("Failed to serialize json: {}", e);
+        });
+        println!("{}", json);
+    }
+}
+
+fn validate_plan(plan_path: &Path, game_db: &GameDatabase, limits: &RequestLimits) {
+    let result = PlanConfig::from_file(plan_path, game_db)
+        .and_then(|mut plan| Ok(plan.apply_limits(limits)?));
+    match result {
+        Ok(..) => println!("Plan {} is valid", plan_path.display()),
+        Err(e) => {
+            eprintln!("Plan {} is invalid: {}", plan_path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn import_plan(
+    plan_path: &Path,
+    csv_path: &Path,
+    game_db: &GameDatabase,
+    limits: &RequestLimits,
+    cache: &mut FullPlanCache,
+) {
+    let graph = solve_plan(&load_plan(plan_path, game_db, limits), cache);
+    let csv = std::fs::read_to_string(csv_path).unwrap_or_else(|e| {
+        panic!("Failed to read csv {}: {}", csv_path.display(), e);
+    });
+    let warnings = compare_with_csv(&graph, &csv).unwrap_or_else(|e| {
+        panic!("Failed to read csv {}: {}", csv_path.display(), e);
     });
 
-    let plan = PlanConfig::from_file(&args.plan, &game_db).unwrap_or_else(|e| {
-        panic!("Failed to load plan {}: {}", args.plan.display(), e);
+    if warnings.is_empty() {
+        println!(
+            "Plan {} matches {}",
+            plan_path.display(),
+            csv_path.display()
+        );
+    } else {
+        for warning in &warnings {
+            println!("Warning: {}", warning);
+        }
+    }
+}
+
+fn load_plan(plan_path: &Path, game_db: &GameDatabase, limits: &RequestLimits) -> PlanConfig {
+    let mut plan = PlanConfig::from_file(plan_path, game_db).unwrap_or_else(|e| {
+        panic!("Failed to load plan {}: {}", plan_path.display(), e);
     });
+    plan.apply_limits(limits).unwrap_or_else(|e| {
+        panic!("Failed to load plan {}: {}", plan_path.display(), e);
+    });
+    plan
+}
+
+fn solve_plan(plan: &PlanConfig, cache: &mut FullPlanCache) -> SolvedGraph {
+    solve_with_cache(plan, cache).unwrap_or_else(|e| {
+        panic!("Failed to solve plan: {}", e);
+    })
+}
+
+fn find_node(graph: &SolvedGraph, id: &str) -> NodeIndex {
+    graph
+        .node_indices()
+        .find(|&i| graph[i].id() == id)
+        .unwrap_or_else(|| panic!("The solved plan has no node with the id `{}`", id))
+}
 
-    if args.full_plan_graph {
-        let graph = crate::plan::build_full_plan(&plan).unwrap_or_else(|e| {
+fn create_plan(
+    plan_path: &Path,
+    full_plan_graph: bool,
+    debug: bool,
+    format: OutputFormat,
+    game_db: &GameDatabase,
+    limits: &RequestLimits,
+    cache: &mut FullPlanCache,
+) {
+    let mut plan = load_plan(plan_path, game_db, limits);
+    plan.debug = debug;
+
+    if full_plan_graph {
+        let graph = build_full_plan(&plan).unwrap_or_else(|e| {
             panic!(
                 "Failed to build full plan graph {}: {}",
-                args.plan.display(),
+                plan_path.display(),
                 e
             );
         });
         print_graph(&graph);
     } else {
-        let graph = solve(&plan).unwrap_or_else(|e| {
+        let start = Instant::now();
+        let solved_plan = solve_with_objective(&plan, cache).unwrap_or_else(|e| {
             panic!("Failed to solve plan: {}", e);
         });
-        print_graph(&graph);
+        let graph = solved_plan.graph;
+        let total_duration = start.elapsed();
+        match format {
+            OutputFormat::Summary => println!("{}", solved_graph_to_dot(&graph, plan.time_unit)),
+            OutputFormat::Csv => {
+                print!("{}", solved_graph_to_csv(&graph, plan.time_unit));
+                return;
+            }
+            OutputFormat::Dot => {
+                println!("{}", solved_graph_to_dot(&graph, plan.time_unit));
+                return;
+            }
+        }
 
-        let mut floor_area = 0.0;
-        let mut volume = 0.0;
         let mut total_buildings = 0.0;
-        let mut power_usage = 0.0;
 
         for i in graph.node_indices() {
-            if let SolvedNodeWeight::Production(recipe, building_count) = &graph[i] {
-                floor_area += recipe.building.floor_area() * building_count.ceil();
-                volume += recipe.building.volume() * building_count.ceil();
+            if let SolvedNodeWeight::Production { building_count, .. } = &graph[i] {
                 total_buildings += building_count.ceil();
-                power_usage += recipe.average_mw(100.0) * building_count.floor();
-
-                let last_clock_speed = building_count.fract() * 100.0;
-                power_usage += recipe.average_mw(last_clock_speed);
             }
         }
 
+        let summary = PlanSummary::from_graph(&graph);
+
         println!("Total Buildings: {}", round(total_buildings, 3));
-        println!("Floor Area: {} m^2", round(floor_area, 3));
-        println!("Volume: {} m^3", round(volume, 3));
-        println!("Power Usage: {} MW", round(power_usage, 3));
+        println!("Floor Area: {} m^2", round(summary.total_floor_area_m2, 3));
+        println!("Volume: {} m^3", round(summary.total_volume_m3, 3));
+        println!("Power Usage: {} MW", round(summary.total_power_mw, 3));
+        if summary.total_power_generated_mw > 0.0 {
+            println!(
+                "Power Generated: {} MW",
+                round(summary.total_power_generated_mw, 3)
+            );
+            println!("Net Power: {} MW", round(summary.net_power_mw, 3));
+        }
+        println!("Objective Value: {}", round(solved_plan.objective_value, 3));
+        if !solved_plan.saturated_resources.is_empty() {
+            println!(
+                "Saturated Resources: {}",
+                solved_plan.saturated_resources.join(", ")
+            );
+        }
+        let time_unit = plan.time_unit;
+        println!(
+            "Sink Points: {} / {}",
+            round(time_unit.rate_in_unit(summary.total_sink_points_per_min), 3),
+            time_unit.abbreviation()
+        );
+        for item in &summary.unsinkable_outputs {
+            println!("Warning: {} can't be sunk", item.name);
+        }
+
+        if summary.has_cycles {
+            println!("Contains Feedback Loops");
+        }
+        if let Some(max_depth) = summary.max_depth {
+            println!("Production Stages: {}", max_depth);
+        }
+        let mut building_totals: Vec<_> = summary.building_totals.iter().collect();
+        building_totals.sort_by(|a, b| a.0.cmp(b.0));
+        println!("Buildings:");
+        for (building, count) in building_totals {
+            println!("  {}: {}", building, round(*count, 3));
+        }
+        println!("Extractors:");
+        for extractor in extractor_counts(&graph, &plan.game_db) {
+            println!(
+                "  {} ({}): {}, {} / {} spare",
+                extractor.building,
+                extractor.resource.name,
+                extractor.count,
+                round(time_unit.rate_in_unit(extractor.spare_capacity_per_min), 3),
+                time_unit.abbreviation()
+            );
+        }
+
+        if let Some(costs) = resource_costs(&graph) {
+            println!("Resource Costs:");
+            for cost in costs {
+                println!(
+                    "  {} -> {}: {} / {}",
+                    cost.resource.name,
+                    cost.output.name,
+                    round(time_unit.rate_in_unit(cost.amount), 3),
+                    time_unit.abbreviation()
+                );
+            }
+        }
+
+        if debug {
+            let graph_build_duration = cache.build_duration();
+            let solve_duration = total_duration.saturating_sub(graph_build_duration);
+            println!(
+                "Graph Build Time: {} ms",
+                round(graph_build_duration.as_secs_f64() * 1000.0, 3)
+            );
+            println!(
+                "Solve Time: {} ms",
+                round(solve_duration.as_secs_f64() * 1000.0, 3)
+            );
+        }
     }
 }
@@ -0,0 +1,153 @@
+use std::rc::Rc;
+
+use crate::{
+    game::{GameDatabase, Item, ItemPerMinute, Recipe},
+    utils::FloatType,
+};
+
+use super::{solve_with_cache, FullPlanCache, PlanConfig, SolvedGraph, SolvedNodeWeight};
+
+/// How much enabling a single alternate recipe would lower a plan's resource score
+#[derive(Debug, Clone)]
+pub struct AlternateImpact {
+    pub recipe: Rc<Recipe>,
+    pub resource_savings: FloatType,
+}
+
+/// Solves the plan once as is and once more for every alternate recipe in `game_db` that
+/// the plan doesn't already enable, each added on its own.  The impacts are ranked from
+/// the largest resource savings to the smallest.
+pub fn alternate_impacts(
+    config: &PlanConfig,
+    game_db: &GameDatabase,
+    cache: &mut FullPlanCache,
+) -> Result<Vec<AlternateImpact>, anyhow::Error> {
+    let base_score = resource_score(config, &solve_with_cache(config, cache)?);
+
+    let mut impacts = Vec::new();
+    for recipe in &game_db.recipes {
+        if !recipe.alternate || config.game_db.recipes.contains(recipe) {
+            continue;
+        }
+
+        let mut alternate_config = config.clone();
+        alternate_config.game_db.recipes.push(Rc::clone(recipe));
+        let graph = solve_with_cache(&alternate_config, cache)?;
+
+        impacts.push(AlternateImpact {
+            recipe: Rc::clone(recipe),
+            resource_savings: base_score - resource_score(config, &graph),
+        });
+    }
+
+    impacts.sort_by(|a, b| b.resource_savings.total_cmp(&a.resource_savings));
+    Ok(impacts)
+}
+
+/// How much of the plan's resources a recipe needs for each item it produces
+#[derive(Debug, Clone)]
+pub struct RecipeEfficiency {
+    pub recipe: Rc<Recipe>,
+    pub resource_score_per_item: FloatType,
+}
+
+/// Solves for a single item per minute of `item` once for every enabled recipe producing
+/// it, with the other recipes for `item` disabled, ignoring the plan's own outputs.
+/// Recipes whose inputs can't be made with the enabled recipes are left out.  The
+/// recipes are ranked from the lowest resource score to the highest.
+pub fn recipe_efficiencies(
+    config: &PlanConfig,
+    item: &Rc<Item>,
+    cache: &mut FullPlanCache,
+) -> Result<Vec<RecipeEfficiency>, anyhow::Error> {
+    let producers = config.game_db.find_recipes_by_output(item);
+
+    let mut efficiencies = Vec::new();
+    for recipe in &producers {
+        let mut recipe_config = config.clone();
+        recipe_config.outputs = vec![ItemPerMinute::new(Rc::clone(item), 1.0)];
+        recipe_config
+            .game_db
+            .recipes
+            .retain(|r| r == recipe || !producers.contains(r));
+
+        if let Ok(graph) = solve_with_cache(&recipe_config, cache) {
+            efficiencies.push(RecipeEfficiency {
+                recipe: Rc::clone(recipe),
+                resource_score_per_item: resource_score(config, &graph),
+            });
+        }
+    }
+
+    efficiencies.sort_by(|a, b| {
+        a.resource_score_per_item
+            .total_cmp(&b.resource_score_per_item)
+    });
+    Ok(efficiencies)
+}
+
+/// The resource inputs' share of their limits, weighted the same way the solver weighs them
+fn resource_score(config: &PlanConfig, graph: &SolvedGraph) -> FloatType {
+    graph
+        .node_weights()
+        .filter_map(|n| match n {
+            SolvedNodeWeight::Input(input) if input.item.resource => {
+                Some(input.amount * config.resource_score(&input.item))
+            }
+            _ => None,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{test::get_test_game_db_with_recipes, ItemPerMinute};
+
+    #[test]
+    fn pure_iron_ingot_impact() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronPlate_C",
+            "Recipe_Alternate_PureIronIngot_C",
+        ]);
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(
+            vec![ItemPerMinute::new(iron_plate, 60.0)],
+            game_db.filter(|r| !r.alternate),
+        );
+
+        let impacts = alternate_impacts(&config, &game_db, &mut FullPlanCache::default()).unwrap();
+        assert_eq!(impacts.len(), 1);
+        assert_eq!(impacts[0].recipe.key, "Recipe_Alternate_PureIronIngot_C");
+        assert!(impacts[0].resource_savings > 0.0);
+    }
+
+    #[test]
+    fn iron_ingot_recipe_efficiencies() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_Alternate_IngotIron_C",
+            "Recipe_Alternate_PureIronIngot_C",
+        ]);
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let config = PlanConfig::new(vec![], game_db);
+
+        let efficiencies =
+            recipe_efficiencies(&config, &iron_ingot, &mut FullPlanCache::default()).unwrap();
+        let recipes: Vec<&str> = efficiencies.iter().map(|e| e.recipe.key.as_str()).collect();
+        assert_eq!(
+            recipes,
+            vec![
+                "Recipe_Alternate_PureIronIngot_C",
+                "Recipe_IngotIron_C",
+                "Recipe_Alternate_IngotIron_C"
+            ]
+        );
+        assert_eq!(
+            efficiencies[1].resource_score_per_item,
+            config.resource_score(&iron_ore)
+        );
+    }
+}
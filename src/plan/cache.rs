@@ -0,0 +1,202 @@
+use std::{
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use super::{full_plan_graph::build_full_plan_before, FullPlanGraph, PlanConfig};
+
+const DEFAULT_CACHE_CAPACITY: usize = 16;
+
+/// Everything about a plan config that affects the shape of its full plan graph.  Input
+/// limits and output amounts only matter to the solver, so they are left out apart from
+/// whether an input is available at all.  The item producers and power generators are
+/// included since game databases merged with mods can differ in those alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FullPlanKey {
+    recipes: Vec<String>,
+    buildings: Vec<String>,
+    outputs: Vec<String>,
+    inputs: Vec<String>,
+    power_output: bool,
+    allow_resource_outputs: bool,
+    max_depth: Option<u32>,
+}
+
+impl FullPlanKey {
+    fn from_config(config: &PlanConfig) -> Self {
+        let mut recipes: Vec<String> = config
+            .game_db
+            .recipes
+            .iter()
+            .map(|r| r.key.clone())
+            .collect();
+        recipes.sort();
+
+        let mut buildings: Vec<String> = config
+            .game_db
+            .buildings
+            .iter()
+            .filter(|b| b.is_item_producer() || b.is_power_generator())
+            .map(|b| b.key().to_string())
+            .collect();
+        buildings.sort();
+
+        let mut outputs: Vec<String> = config.outputs.iter().map(|o| o.item.key.clone()).collect();
+        outputs.sort();
+
+        let mut inputs: Vec<String> = config
+            .inputs
+            .keys()
+            .filter(|item| config.has_input(item))
+            .map(|item| item.key.clone())
+            .collect();
+        inputs.sort();
+
+        Self {
+            recipes,
+            buildings,
+            outputs,
+            inputs,
+            power_output: config.power_output_mw > 0.0,
+            allow_resource_outputs: config.allow_resource_outputs,
+            max_depth: config.max_depth,
+        }
+    }
+}
+
+/// A least recently used cache of full plan graphs so that re-solving a plan with
+/// different limits or amounts can skip rebuilding the graph.
+#[derive(Debug)]
+pub struct FullPlanCache {
+    capacity: usize,
+    entries: VecDeque<(FullPlanKey, Rc<FullPlanGraph>)>,
+    builds: usize,
+    build_duration: Duration,
+}
+
+impl Default for FullPlanCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl FullPlanCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: VecDeque::new(),
+            builds: 0,
+            build_duration: Duration::ZERO,
+        }
+    }
+
+    /// Returns the plan's full graph, building it if it isn't cached.  Building gives up
+    /// with `RequestTooLarge` once `deadline` has passed.
+    pub fn get_or_build(
+        &mut self,
+        config: &PlanConfig,
+        deadline: Option<Instant>,
+    ) -> anyhow::Result<Rc<FullPlanGraph>> {
+        let key = FullPlanKey::from_config(config);
+
+        if let Some(pos) = self.entries.iter().position(|(k, _)| *k == key) {
+            let entry = self.entries.remove(pos).unwrap();
+            let graph = Rc::clone(&entry.1);
+            self.entries.push_back(entry);
+            return Ok(graph);
+        }
+
+        let start = Instant::now();
+        let graph = Rc::new(build_full_plan_before(config, deadline)?);
+        self.build_duration += start.elapsed();
+        self.builds += 1;
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, Rc::clone(&graph)));
+
+        Ok(graph)
+    }
+
+    /// The number of times a full plan graph had to be built because of a cache miss
+    #[cfg(test)]
+    pub fn builds(&self) -> usize {
+        self.builds
+    }
+
+    /// The total time spent building full plan graphs
+    pub fn build_duration(&self) -> Duration {
+        self.build_duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{test::get_test_game_db_with_recipes, ItemPerMinute},
+        plan::solve_with_cache,
+    };
+
+    #[test]
+    fn graph_build_is_skipped_on_cache_hit() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+
+        let mut cache = FullPlanCache::default();
+
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        solve_with_cache(&config, &mut cache).unwrap();
+        assert_eq!(cache.builds(), 1);
+
+        config.outputs[0].amount = 60.0;
+        let graph = solve_with_cache(&config, &mut cache).unwrap();
+        assert_eq!(cache.builds(), 1);
+        assert_eq!(graph.node_count(), 4);
+
+        config.outputs.push(ItemPerMinute::new(iron_ingot, 10.0));
+        solve_with_cache(&config, &mut cache).unwrap();
+        assert_eq!(cache.builds(), 2);
+    }
+
+    #[test]
+    fn least_recently_used_entry_is_evicted() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+
+        let plate_config =
+            PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db.clone());
+        let ingot_config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 40.0)], game_db);
+
+        let mut cache = FullPlanCache::new(1);
+        cache.get_or_build(&plate_config, None).unwrap();
+        cache.get_or_build(&ingot_config, None).unwrap();
+        cache.get_or_build(&plate_config, None).unwrap();
+
+        assert_eq!(cache.builds(), 3);
+    }
+
+    #[test]
+    fn generator_buildings_are_part_of_the_key() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 30.0)], game_db);
+        config.power_output_mw = 75.0;
+
+        let mut cache = FullPlanCache::default();
+        cache.get_or_build(&config, None).unwrap();
+
+        config
+            .game_db
+            .buildings
+            .retain(|b| b.key() != "Desc_GeneratorFuel_C");
+        cache.get_or_build(&config, None).unwrap();
+
+        assert_eq!(cache.builds(), 2);
+    }
+}
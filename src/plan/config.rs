@@ -6,39 +6,39 @@ use std::fmt;
 use std::fs::File;
 use std::path::Path;
 use std::rc::Rc;
-use thiserror::Error;
-
-use crate::game::{GameDatabase, Item, ItemPerMinute, Recipe};
-use crate::utils::FloatType;
-
-#[derive(Error, Debug, Eq, PartialEq)]
-pub enum PlanError {
-    #[error("No recipe exists with the name or key `{0}`")]
-    UnknownRecipe(String),
-    #[error("No item exists with the name or key `{0}`")]
-    UnknownItem(String),
-    #[error("The resource `{0}` is not allowed in outputs.")]
-    UnexpectedResource(String),
-}
+use std::time::Duration;
+
+use crate::game::logistics::{BeltTier, PipeTier};
+use crate::game::{GameDatabase, Item, ItemPerMinute, Recipe, ResourcePreset};
+use crate::utils::{round, FloatType, EPSILON};
+
+use super::PlanError;
+
+/// The resource score of using up a resource's entire limit
+const RESOURCE_SCORE_SCALE: FloatType = 10_000.0;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 enum RecipeMatcher {
+    IncludeAll,
     IncludeBase,
     IncludeAlternate,
     IncludeByNameOrKey(String),
     IncludeByOutputItem(String),
     ExcludeByNameOrKey(String),
     IncludeByEvent(String),
+    IncludeByTag(String),
 }
 
 impl RecipeMatcher {
     pub fn is_include(&self) -> bool {
         match self {
+            Self::IncludeAll => true,
             Self::IncludeBase => true,
             Self::IncludeAlternate => true,
             Self::IncludeByNameOrKey(..) => true,
             Self::IncludeByOutputItem(..) => true,
             Self::IncludeByEvent(..) => true,
+            Self::IncludeByTag(..) => true,
             Self::ExcludeByNameOrKey(..) => false,
         }
     }
@@ -53,7 +53,10 @@ impl RecipeMatcher {
                 {
                     Ok(())
                 } else {
-                    Err(PlanError::UnknownRecipe(name.clone()))
+                    Err(PlanError::UnknownRecipe(
+                        name.clone(),
+                        game_db.suggest_recipes(name),
+                    ))
                 }
             }
             Self::ExcludeByNameOrKey(name) => {
@@ -64,7 +67,10 @@ impl RecipeMatcher {
                 {
                     Ok(())
                 } else {
-                    Err(PlanError::UnknownRecipe(name.clone()))
+                    Err(PlanError::UnknownRecipe(
+                        name.clone(),
+                        game_db.suggest_recipes(name),
+                    ))
                 }
             }
             Self::IncludeByOutputItem(item) => {
@@ -75,7 +81,10 @@ impl RecipeMatcher {
                 {
                     Ok(())
                 } else {
-                    Err(PlanError::UnknownItem(item.clone()))
+                    Err(PlanError::UnknownItem(
+                        item.clone(),
+                        game_db.suggest_items(item),
+                    ))
                 }
             }
             _ => Ok(()),
@@ -84,6 +93,7 @@ impl RecipeMatcher {
 
     pub fn matches(&self, recipe: &Recipe) -> bool {
         match self {
+            Self::IncludeAll => true,
             Self::IncludeBase => recipe.events.is_empty() && !recipe.alternate,
             Self::IncludeAlternate => recipe.alternate,
             Self::IncludeByNameOrKey(name) => {
@@ -99,6 +109,7 @@ impl RecipeMatcher {
             Self::IncludeByEvent(event) => {
                 recipe.events.iter().any(|e| e.eq_ignore_ascii_case(event))
             }
+            Self::IncludeByTag(tag) => recipe.has_tag(tag),
         }
     }
 }
@@ -120,7 +131,7 @@ impl<'de> Visitor<'de> for RecipeMatcherVisitor {
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         write!(
             formatter,
-            "base, alternates, recipe name, output: item name, exclude: recipe name"
+            "all, base, alternates, recipe name, output: item name, exclude: recipe name, tag: recipe tag"
         )
     }
 
@@ -128,10 +139,14 @@ impl<'de> Visitor<'de> for RecipeMatcherVisitor {
     where
         E: serde::de::Error,
     {
-        if v.eq_ignore_ascii_case("base") {
+        if v.eq_ignore_ascii_case("all") {
+            Ok(RecipeMatcher::IncludeAll)
+        } else if v.eq_ignore_ascii_case("base") {
             Ok(RecipeMatcher::IncludeBase)
         } else if v.eq_ignore_ascii_case("alternates") || v.eq_ignore_ascii_case("alts") {
             Ok(RecipeMatcher::IncludeAlternate)
+        } else if let Some(tag) = v.strip_prefix("tag:") {
+            Ok(RecipeMatcher::IncludeByTag(tag.trim().into()))
         } else {
             Ok(RecipeMatcher::IncludeByNameOrKey(v.into()))
         }
@@ -148,6 +163,8 @@ impl<'de> Visitor<'de> for RecipeMatcherVisitor {
                 Ok(RecipeMatcher::IncludeByOutputItem(map.next_value()?))
             } else if field.eq_ignore_ascii_case("event") {
                 Ok(RecipeMatcher::IncludeByEvent(map.next_value()?))
+            } else if field.eq_ignore_ascii_case("tag") {
+                Ok(RecipeMatcher::IncludeByTag(map.next_value()?))
             } else {
                 Err(serde::de::Error::custom(format!(
                     "Unknown recipe matcher {}",
@@ -160,29 +177,312 @@ impl<'de> Visitor<'de> for RecipeMatcherVisitor {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// An input amount in items per minute, or `unlimited` for an item that is freely
+/// available.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct InputAmount(FloatType);
+
+impl<'de> Deserialize<'de> for InputAmount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(InputAmountVisitor)
+    }
+}
+
+struct InputAmountVisitor;
+
+impl<'de> Visitor<'de> for InputAmountVisitor {
+    type Value = InputAmount;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a number or unlimited")
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(InputAmount(v as FloatType))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(InputAmount(v as FloatType))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(InputAmount(v as FloatType))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        if v.eq_ignore_ascii_case("unlimited") {
+            Ok(InputAmount(FloatType::INFINITY))
+        } else {
+            Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(v),
+                &self,
+            ))
+        }
+    }
+}
+
+/// An output amount in items per minute, or as the output of a number of buildings
+/// running a recipe at 100% clock speed, e.g. `{ recipe: Iron Ingot, buildings: 2 }`
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum OutputAmount {
+    PerMinute(FloatType),
+    Buildings {
+        recipe: String,
+        buildings: FloatType,
+    },
+}
+
+/// The time unit the amounts in a plan are given in.  They are converted to per minute
+/// when the plan is loaded and back to this unit wherever a solved plan is reported.
+#[derive(Debug, Copy, Clone, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeUnit {
+    Second,
+    #[default]
+    Minute,
+    Hour,
+}
+
+impl TimeUnit {
+    /// How many minutes are in one of this unit
+    pub fn minutes(self) -> FloatType {
+        match self {
+            Self::Second => 1.0 / 60.0,
+            Self::Minute => 1.0,
+            Self::Hour => 60.0,
+        }
+    }
+
+    pub fn rate_per_minute(self, amount: FloatType) -> FloatType {
+        amount / self.minutes()
+    }
+
+    pub fn rate_in_unit(self, amount: FloatType) -> FloatType {
+        amount * self.minutes()
+    }
+
+    pub fn abbreviation(self) -> &'static str {
+        match self {
+            Self::Second => "s",
+            Self::Minute => "min",
+            Self::Hour => "h",
+        }
+    }
+
+    /// Formats a per minute rate in this unit, e.g. `1.5 / s`
+    pub fn format_rate(self, amount: FloatType) -> String {
+        format!(
+            "{} / {}",
+            round(self.rate_in_unit(amount), 3),
+            self.abbreviation()
+        )
+    }
+}
+
+/// What the solver optimizes for.  `minimize` produces exactly the requested outputs
+/// for the lowest weighted cost, while `sink_points` treats the outputs as minimums and
+/// maximizes the AWESOME Sink points of everything produced, with each output's points
+/// scaled by its `output_weights` entry.  `simplicity` finds the
+/// same lowest cost as `minimize` but prefers the plan using the fewest recipes.
+/// `minimize_resource` only minimizes the usage of `target_resource`, leaving every other
+/// resource free up to its limit.  `min_inputs` uses as few distinct resources as possible,
+/// even when that costs more of them, then minimizes like `minimize`.  `maximize_output`
+/// treats the outputs as minimums and produces as much of `target_output` as the inputs
+/// allow.
+#[derive(Debug, Copy, Clone, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectiveMode {
+    #[default]
+    Minimize,
+    SinkPoints,
+    Simplicity,
+    MinimizeResource,
+    MinInputs,
+    MaximizeOutput,
+}
+
+/// Relative weights of each term in the solver's objective.  Each term is
+/// summed over every production node, scaled by its building count:
+///
+/// * `resource` - resource usage, normalized against each resource's limit so
+///   that consuming an entire resource limit costs `10^8`.  Individual resources
+///   can be scaled further with `resource_weights`.
+/// * `power` - average power draw in MW at 100% clock speed.
+/// * `floor_area` - building footprint in m^2.
+/// * `volume` - building volume in m^3.
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ObjectiveWeights {
+    pub resource: FloatType,
+    pub power: FloatType,
+    pub floor_area: FloatType,
+    pub volume: FloatType,
+}
+
+impl Default for ObjectiveWeights {
+    fn default() -> Self {
+        Self {
+            resource: 1.0,
+            power: 0.0,
+            floor_area: 0.0,
+            volume: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
 struct PlanConfigDefinition {
     #[serde(default)]
-    inputs: HashMap<String, FloatType>,
-    outputs: IndexMap<String, FloatType>,
+    inputs: HashMap<String, InputAmount>,
+    #[serde(default)]
+    outputs: IndexMap<String, OutputAmount>,
+    #[serde(default)]
+    resource_preset: Option<ResourcePreset>,
+    #[serde(default)]
+    resource_limits: HashMap<String, FloatType>,
+    #[serde(default)]
+    resource_weights: HashMap<String, FloatType>,
+    #[serde(default)]
+    output_weights: HashMap<String, FloatType>,
+    #[serde(default)]
+    power_output_mw: FloatType,
+    #[serde(default)]
+    max_power_mw: Option<FloatType>,
+    #[serde(default)]
     enabled_recipes: Vec<RecipeMatcher>,
+    #[serde(default)]
+    disabled_recipes: Vec<String>,
+    #[serde(default)]
+    enable_all: bool,
+    #[serde(default)]
+    exclude_events: bool,
+    #[serde(default)]
+    allow_resource_outputs: bool,
+    #[serde(default)]
+    objective: ObjectiveMode,
+    #[serde(default)]
+    objective_weights: ObjectiveWeights,
+    #[serde(default)]
+    target_resource: Option<String>,
+    #[serde(default)]
+    target_output: Option<String>,
+    #[serde(default)]
+    max_buildings: Option<u32>,
+    #[serde(default)]
+    max_depth: Option<u32>,
+    #[serde(default, alias = "producer_limits")]
+    building_limits: HashMap<String, FloatType>,
+    #[serde(default)]
+    round_to_whole_buildings: bool,
+    #[serde(default)]
+    integer_buildings: bool,
+    #[serde(default)]
+    round_digits: Option<u8>,
+    #[serde(default)]
+    promote_byproducts_to_outputs: bool,
+    #[serde(default)]
+    no_waste: bool,
+    #[serde(default)]
+    no_surplus: Vec<String>,
+    #[serde(default)]
+    waste_penalty: FloatType,
+    #[serde(default, alias = "fixed_production")]
+    pinned: HashMap<String, FloatType>,
+    #[serde(default)]
+    epsilon: Option<FloatType>,
+    #[serde(default)]
+    keep_unused: bool,
+    #[serde(default)]
+    belt_tier: BeltTier,
+    #[serde(default)]
+    pipe_tier: PipeTier,
+    #[serde(default)]
+    time_unit: TimeUnit,
+}
+
+/// Caps on how large a plan can be, for when plans come from untrusted sources.  Every
+/// limit is off by default.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct RequestLimits {
+    pub max_outputs: Option<usize>,
+    pub max_recipes: Option<usize>,
+    pub solve_timeout: Option<Duration>,
 }
 
 #[derive(Debug, Clone)]
 pub struct PlanConfig {
     pub inputs: HashMap<Rc<Item>, FloatType>,
     pub outputs: Vec<ItemPerMinute>,
+    pub power_output_mw: FloatType,
+    /// The most average power, in MW, the production buildings can draw combined
+    pub max_power_mw: Option<FloatType>,
+    pub objective: ObjectiveMode,
+    pub objective_weights: ObjectiveWeights,
+    pub target_resource: Option<Rc<Item>>,
+    /// The output to produce as much of as possible when maximizing an output
+    pub target_output: Option<Rc<Item>>,
+    pub resource_weights: HashMap<Rc<Item>, FloatType>,
+    /// Scales the sink points of each output when maximizing sink points
+    pub output_weights: HashMap<Rc<Item>, FloatType>,
+    pub max_buildings: Option<u32>,
+    /// The most recipes that can be chained together to produce an output or fuel
+    pub max_depth: Option<u32>,
+    /// The most of each building, keyed by building key, that the plan can use.  This
+    /// includes producers of free items, e.g. FICSMAS Gift Trees.
+    pub building_limits: HashMap<String, FloatType>,
+    pub round_to_whole_buildings: bool,
+    pub integer_buildings: bool,
+    /// Rounds the solved item amounts to this many decimals when set
+    pub round_digits: Option<u8>,
+    pub promote_byproducts_to_outputs: bool,
+    pub no_waste: bool,
+    /// Items that can be produced as intermediates, but never left over as by-products
+    pub no_surplus_items: Vec<Rc<Item>>,
+    /// How much by-products left unused count against the plan when minimizing.  At 1.0,
+    /// an item per minute of by-products costs as much as an item per minute of a resource
+    /// limited to 10,000 / min.
+    pub waste_penalty: FloatType,
+    pub allow_resource_outputs: bool,
+    /// Building counts, keyed by recipe key, that the solver must use as is while the rest
+    /// of the plan is re-optimized around them
+    pub pinned_recipes: HashMap<String, FloatType>,
+    /// Amounts smaller than this are treated as zero when copying the solution and
+    /// cleaning up by-products
+    pub epsilon: FloatType,
+    /// Keeps the production nodes of recipes the solution doesn't use in the solved graph,
+    /// with no buildings
+    pub keep_unused: bool,
+    pub belt_tier: BeltTier,
+    pub pipe_tier: PipeTier,
+    /// The unit the plan's input, output and resource limit amounts were given in, and
+    /// that every printed rate is reported in
+    pub time_unit: TimeUnit,
+    pub solve_timeout: Option<Duration>,
+    /// Reports solver passes that fail without failing the solve, e.g. the tie-break
+    pub debug: bool,
     pub game_db: GameDatabase,
 }
 
 #[allow(dead_code)]
 impl PlanConfig {
     pub fn new(outputs: Vec<ItemPerMinute>, game_db: GameDatabase) -> Self {
-        PlanConfig {
-            inputs: game_db.resource_limits.clone(),
-            outputs,
-            game_db,
-        }
+        Self::with_inputs(HashMap::new(), outputs, game_db)
     }
 
     pub fn with_inputs(
@@ -196,6 +496,33 @@ impl PlanConfig {
         PlanConfig {
             inputs: all_inputs,
             outputs,
+            power_output_mw: 0.0,
+            max_power_mw: None,
+            objective: ObjectiveMode::default(),
+            objective_weights: ObjectiveWeights::default(),
+            target_resource: None,
+            target_output: None,
+            resource_weights: HashMap::new(),
+            output_weights: HashMap::new(),
+            max_buildings: None,
+            max_depth: None,
+            building_limits: HashMap::new(),
+            round_to_whole_buildings: false,
+            integer_buildings: false,
+            round_digits: None,
+            promote_byproducts_to_outputs: false,
+            no_waste: false,
+            no_surplus_items: Vec::new(),
+            waste_penalty: 0.0,
+            allow_resource_outputs: false,
+            pinned_recipes: HashMap::new(),
+            epsilon: EPSILON,
+            keep_unused: false,
+            belt_tier: BeltTier::default(),
+            pipe_tier: PipeTier::default(),
+            time_unit: TimeUnit::default(),
+            solve_timeout: None,
+            debug: false,
             game_db,
         }
     }
@@ -208,45 +535,244 @@ impl PlanConfig {
     }
 
     fn convert(config: PlanConfigDefinition, game_db: &GameDatabase) -> Result<Self, PlanError> {
-        // validate there are no extractable resources in the outputs list
+        // validate there are no extractable resources in the outputs list, unless they've
+        // been explicitly allowed
         let mut outputs = Vec::new();
-        for (item_name, value) in config.outputs {
-            let item = game_db
-                .find_item(&item_name)
-                .ok_or(PlanError::UnknownItem(item_name))?;
-            if item.resource {
+        for (item_name, amount) in config.outputs {
+            let item = find_unique_item(game_db, item_name)?;
+            if item.resource && !config.allow_resource_outputs {
                 return Err(PlanError::UnexpectedResource(item.name.clone()));
             }
 
+            let value = match amount {
+                OutputAmount::PerMinute(value) => config.time_unit.rate_per_minute(value),
+                OutputAmount::Buildings { recipe, buildings } => {
+                    let recipe = game_db.find_recipe(&recipe).ok_or_else(|| {
+                        PlanError::UnknownRecipe(recipe.clone(), game_db.suggest_recipes(&recipe))
+                    })?;
+                    if !recipe.is_primary_output(&item) {
+                        return Err(PlanError::NotPrimaryOutput(
+                            recipe.name.clone(),
+                            item.name.clone(),
+                        ));
+                    }
+
+                    recipe.outputs[0].amount * buildings
+                }
+            };
+
             outputs.push(ItemPerMinute::new(item, value))
         }
 
-        let mut inputs: HashMap<Rc<Item>, FloatType> = game_db.resource_limits.clone();
-        for (item_name, value) in config.inputs {
-            let item = game_db
-                .find_item(&item_name)
-                .ok_or(PlanError::UnknownItem(item_name))?;
+        // the target output doesn't need a minimum, so it is added as an output if missing
+        let target_output = match (config.objective, config.target_output) {
+            (ObjectiveMode::MaximizeOutput, None) => return Err(PlanError::MissingTargetOutput),
+            (_, Some(item_name)) => {
+                let item = find_unique_item(game_db, item_name)?;
+                if item.resource && !config.allow_resource_outputs {
+                    return Err(PlanError::UnexpectedResource(item.name.clone()));
+                }
+                if !outputs.iter().any(|o| o.item == item) {
+                    outputs.push(ItemPerMinute::new(Rc::clone(&item), 0.0));
+                }
+                Some(item)
+            }
+            (_, None) => None,
+        };
+
+        // pinned recipes are keyed by recipe key and implicitly enabled
+        let mut pinned_recipes = HashMap::new();
+        for (recipe_name, building_count) in config.pinned {
+            let recipe = game_db.find_recipe(&recipe_name).ok_or_else(|| {
+                PlanError::UnknownRecipe(recipe_name.clone(), game_db.suggest_recipes(&recipe_name))
+            })?;
+            pinned_recipes.insert(recipe.key.clone(), building_count);
+        }
 
-            inputs.insert(item, value);
+        let mut building_limits = HashMap::new();
+        for (building_name, limit) in config.building_limits {
+            let building = game_db
+                .find_building(&building_name)
+                .ok_or(PlanError::UnknownBuilding(building_name))?;
+            building_limits.insert(building.key().to_string(), limit);
         }
 
-        for matcher in &config.enabled_recipes {
+        let mut recipe_matchers = config.enabled_recipes;
+        if config.enable_all {
+            recipe_matchers.push(RecipeMatcher::IncludeAll);
+        }
+        recipe_matchers.extend(
+            pinned_recipes
+                .keys()
+                .cloned()
+                .map(RecipeMatcher::IncludeByNameOrKey),
+        );
+        recipe_matchers.extend(
+            config
+                .disabled_recipes
+                .into_iter()
+                .map(RecipeMatcher::ExcludeByNameOrKey),
+        );
+
+        for matcher in &recipe_matchers {
             matcher.validate(game_db)?;
         }
 
         let (include_matchers, exclude_matchers): (Vec<_>, Vec<_>) =
-            config.enabled_recipes.iter().partition(|m| m.is_include());
+            recipe_matchers.iter().partition(|m| m.is_include());
+
+        // event recipes are only kept when exclude_events is set if they were enabled
+        // by name or key
+        let is_excluded_event = |recipe: &Recipe| {
+            config.exclude_events
+                && !recipe.events.is_empty()
+                && !include_matchers.iter().any(|m| {
+                    matches!(m, RecipeMatcher::IncludeByNameOrKey(..)) && m.matches(recipe)
+                })
+        };
+
+        let mut game_db = game_db.filter(|recipe| {
+            include_matchers.iter().any(|m| m.matches(recipe))
+                && !exclude_matchers.iter().any(|m| m.matches(recipe))
+                && !is_excluded_event(recipe)
+        });
+
+        // the resource preset and limit overrides replace the game database's limits, so
+        // they also affect how resource usage is normalized in the objective
+        if let Some(preset) = &config.resource_preset {
+            let preset_limits = preset
+                .resource_limits(&game_db)
+                .map_err(PlanError::InvalidResourcePreset)?;
+            game_db.resource_limits.extend(preset_limits);
+        }
+
+        for (item_name, limit) in config.resource_limits {
+            let item = find_unique_item(&game_db, item_name)?;
+            if !item.resource {
+                return Err(PlanError::NotAResource(item.name.clone()));
+            }
+
+            game_db
+                .resource_limits
+                .insert(item, config.time_unit.rate_per_minute(limit));
+        }
+
+        let mut resource_weights = HashMap::new();
+        for (item_name, weight) in config.resource_weights {
+            let item = find_unique_item(&game_db, item_name)?;
+            if !item.resource {
+                return Err(PlanError::NotAResource(item.name.clone()));
+            }
+
+            resource_weights.insert(item, weight);
+        }
+
+        let mut output_weights = HashMap::new();
+        for (item_name, weight) in config.output_weights {
+            let item = find_unique_item(&game_db, item_name)?;
+            output_weights.insert(item, weight);
+        }
+
+        let no_surplus_items = config
+            .no_surplus
+            .into_iter()
+            .map(|item_name| find_unique_item(&game_db, item_name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let target_resource = match (config.objective, config.target_resource) {
+            (ObjectiveMode::MinimizeResource, None) => {
+                return Err(PlanError::MissingTargetResource)
+            }
+            (_, Some(item_name)) => {
+                let item = find_unique_item(&game_db, item_name)?;
+                if !item.resource {
+                    return Err(PlanError::NotAResource(item.name.clone()));
+                }
+                Some(item)
+            }
+            (_, None) => None,
+        };
+
+        let mut inputs: HashMap<Rc<Item>, FloatType> = game_db.resource_limits.clone();
+        for (item_name, InputAmount(value)) in config.inputs {
+            let item = find_unique_item(&game_db, item_name)?;
+
+            inputs.insert(item, config.time_unit.rate_per_minute(value));
+        }
 
         Ok(PlanConfig {
             inputs,
             outputs,
-            game_db: game_db.filter(|recipe| {
-                include_matchers.iter().any(|m| m.matches(recipe))
-                    && !exclude_matchers.iter().any(|m| m.matches(recipe))
-            }),
+            power_output_mw: config.power_output_mw,
+            max_power_mw: config.max_power_mw,
+            objective: config.objective,
+            objective_weights: config.objective_weights,
+            target_resource,
+            target_output,
+            resource_weights,
+            output_weights,
+            max_buildings: config.max_buildings,
+            max_depth: config.max_depth,
+            building_limits,
+            round_to_whole_buildings: config.round_to_whole_buildings,
+            integer_buildings: config.integer_buildings,
+            round_digits: config.round_digits,
+            promote_byproducts_to_outputs: config.promote_byproducts_to_outputs,
+            no_waste: config.no_waste,
+            no_surplus_items,
+            waste_penalty: config.waste_penalty,
+            allow_resource_outputs: config.allow_resource_outputs,
+            pinned_recipes,
+            epsilon: config.epsilon.unwrap_or(EPSILON),
+            keep_unused: config.keep_unused,
+            belt_tier: config.belt_tier,
+            pipe_tier: config.pipe_tier,
+            time_unit: config.time_unit,
+            solve_timeout: None,
+            debug: false,
+            game_db,
         })
     }
 
+    /// Checks the plan against the limits and applies the solve timeout
+    pub fn apply_limits(&mut self, limits: &RequestLimits) -> Result<(), PlanError> {
+        if let Some(max_outputs) = limits.max_outputs {
+            if self.outputs.len() > max_outputs {
+                return Err(PlanError::RequestTooLarge(format!(
+                    "{} outputs exceeds the limit of {}",
+                    self.outputs.len(),
+                    max_outputs
+                )));
+            }
+        }
+
+        if let Some(max_recipes) = limits.max_recipes {
+            if self.game_db.recipes.len() > max_recipes {
+                return Err(PlanError::RequestTooLarge(format!(
+                    "{} enabled recipes exceeds the limit of {}",
+                    self.game_db.recipes.len(),
+                    max_recipes
+                )));
+            }
+        }
+
+        self.solve_timeout = limits.solve_timeout;
+        Ok(())
+    }
+
+    /// The keys of every recipe the plan can use, sorted, after tags, `enable_all`,
+    /// disabled recipes and event exclusion have been applied
+    pub fn enabled_recipe_keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self
+            .game_db
+            .recipes
+            .iter()
+            .map(|recipe| recipe.key.as_str())
+            .collect();
+        keys.sort();
+        keys
+    }
+
     pub fn has_input(&self, item: &Rc<Item>) -> bool {
         self.find_input(item) > 0.0
     }
@@ -255,6 +781,28 @@ impl PlanConfig {
         self.inputs.get(item).copied().unwrap_or(0.0)
     }
 
+    /// How much using a resource counts against the plan relative to other resources
+    pub fn find_resource_weight(&self, item: &Rc<Item>) -> FloatType {
+        self.resource_weights.get(item).copied().unwrap_or(1.0)
+    }
+
+    /// How much each item per minute of a resource counts against the plan, normalized
+    /// against the resource's limit so that plans are scored the same way whatever the
+    /// scale of the game database's limits
+    pub fn resource_score(&self, item: &Rc<Item>) -> FloatType {
+        let limit = self.game_db.get_resource_limit(item);
+        if limit > 0.0 {
+            self.find_resource_weight(item) * RESOURCE_SCORE_SCALE / limit
+        } else {
+            0.0
+        }
+    }
+
+    /// How much an output's sink points count for relative to other outputs
+    pub fn find_output_weight(&self, item: &Rc<Item>) -> FloatType {
+        self.output_weights.get(item).copied().unwrap_or(1.0)
+    }
+
     pub fn find_output(&self, item: &Item) -> FloatType {
         self.outputs
             .iter()
@@ -264,15 +812,86 @@ impl PlanConfig {
     }
 }
 
+/// Builds a plan in code instead of from a plan file.  Items and recipes are given by
+/// name or key and are validated against the game database the same way as a plan file's
+/// when the plan is built.
+pub struct PlanConfigBuilder<'a> {
+    game_db: &'a GameDatabase,
+    definition: PlanConfigDefinition,
+}
+
+impl PlanConfig {
+    pub fn builder(game_db: &GameDatabase) -> PlanConfigBuilder<'_> {
+        PlanConfigBuilder {
+            game_db,
+            definition: PlanConfigDefinition::default(),
+        }
+    }
+}
+
+impl PlanConfigBuilder<'_> {
+    pub fn output(mut self, item: &str, amount: FloatType) -> Self {
+        self.definition
+            .outputs
+            .insert(item.into(), OutputAmount::PerMinute(amount));
+        self
+    }
+
+    pub fn input(mut self, item: &str, limit: FloatType) -> Self {
+        self.definition
+            .inputs
+            .insert(item.into(), InputAmount(limit));
+        self
+    }
+
+    pub fn enable_recipe(mut self, recipe: &str) -> Self {
+        self.definition
+            .enabled_recipes
+            .push(RecipeMatcher::IncludeByNameOrKey(recipe.into()));
+        self
+    }
+
+    /// Produces as much of `item` as the inputs allow.  It has no minimum unless one is
+    /// also given with `output`.
+    pub fn maximize(mut self, item: &str) -> Self {
+        self.definition.objective = ObjectiveMode::MaximizeOutput;
+        self.definition.target_output = Some(item.into());
+        self
+    }
+
+    pub fn build(self) -> Result<PlanConfig, PlanError> {
+        PlanConfig::convert(self.definition, self.game_db)
+    }
+}
+
+fn find_unique_item(game_db: &GameDatabase, item_name: String) -> Result<Rc<Item>, PlanError> {
+    let mut items = game_db.find_items(&item_name);
+    match items.len() {
+        0 => {
+            let suggestions = game_db.suggest_items(&item_name);
+            Err(PlanError::UnknownItem(item_name, suggestions))
+        }
+        1 => Ok(items.remove(0)),
+        _ => Err(PlanError::AmbiguousItem(
+            item_name,
+            items.iter().map(|i| i.key.clone()).collect(),
+        )),
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::game::test::get_test_game_db;
+    use crate::{
+        game::test::get_test_game_db,
+        plan::{solve, SolvedNodeWeight},
+    };
 
     use super::*;
 
     #[test]
     fn recipe_matcher_deserialize() {
         let yaml = "#
+            - all
             - base
             - alts
             - alternates
@@ -280,6 +899,8 @@ mod test {
             - exclude: Iron Alloy Ingot
             - output: Copper Ingot
             - event: FICSMAS
+            - tag: aluminum
+            - tag:oil
         #";
 
         let result: Result<Vec<RecipeMatcher>, serde_yaml::Error> = serde_yaml::from_str(yaml);
@@ -288,13 +909,16 @@ mod test {
         assert_eq!(
             result.unwrap(),
             vec![
+                RecipeMatcher::IncludeAll,
                 RecipeMatcher::IncludeBase,
                 RecipeMatcher::IncludeAlternate,
                 RecipeMatcher::IncludeAlternate,
                 RecipeMatcher::IncludeByNameOrKey("Pure Iron Ingot".into()),
                 RecipeMatcher::ExcludeByNameOrKey("Iron Alloy Ingot".into()),
                 RecipeMatcher::IncludeByOutputItem("Copper Ingot".into()),
-                RecipeMatcher::IncludeByEvent("FICSMAS".into())
+                RecipeMatcher::IncludeByEvent("FICSMAS".into()),
+                RecipeMatcher::IncludeByTag("aluminum".into()),
+                RecipeMatcher::IncludeByTag("oil".into())
             ]
         );
     }
@@ -417,4 +1041,603 @@ mod test {
         assert!(ficsmas_matcher.matches(&actual_snow));
         assert!(!ficsmas_matcher.matches(&copper_ingot));
     }
+
+    #[test]
+    fn recipe_matcher_include_by_tag_matches() {
+        let game_db = get_test_game_db();
+
+        let tag_matcher = RecipeMatcher::IncludeByTag("aluminum".into());
+        let mut alumina_solution = game_db
+            .find_recipe("Recipe_AluminaSolution_C")
+            .unwrap()
+            .as_ref()
+            .clone();
+        alumina_solution.tags.push("Aluminum".into());
+        let copper_ingot = game_db.find_recipe("Recipe_IngotCopper_C").unwrap();
+
+        assert!(tag_matcher.matches(&alumina_solution));
+        assert!(!tag_matcher.matches(&copper_ingot));
+    }
+
+    #[test]
+    fn enable_all_with_disabled_recipes() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Ingot: 30
+            enable_all: true
+            disabled_recipes:
+              - Pure Iron Ingot
+              - Recipe_IngotCopper_C
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let config = PlanConfig::convert(definition, &game_db).unwrap();
+
+        assert_eq!(config.game_db.recipes.len(), game_db.recipes.len() - 2);
+        assert!(config
+            .game_db
+            .find_recipe("Recipe_Alternate_PureIronIngot_C")
+            .is_none());
+        assert!(config.game_db.find_recipe("Recipe_IngotCopper_C").is_none());
+        assert!(config.game_db.find_recipe("Recipe_IngotIron_C").is_some());
+    }
+
+    #[test]
+    fn exclude_events_with_enable_all() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Plate: 10
+            enable_all: true
+            exclude_events: true
+            enabled_recipes:
+              - Recipe_XmasBall1_C
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let config = PlanConfig::convert(definition, &game_db).unwrap();
+
+        assert!(config.game_db.find_recipe("Recipe_XmasBall1_C").is_some());
+        assert!(config.game_db.find_recipe("Recipe_XmasBall2_C").is_none());
+        assert!(config
+            .game_db
+            .find_recipe("Recipe_XmasBallCluster_C")
+            .is_none());
+        assert!(config
+            .game_db
+            .recipes
+            .iter()
+            .filter(|r| !r.events.is_empty())
+            .all(|r| r.key == "Recipe_XmasBall1_C"));
+        assert!(config.game_db.find_recipe("Recipe_IronPlate_C").is_some());
+    }
+
+    #[test]
+    fn disabled_recipes_unknown_recipe() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Ingot: 30
+            enable_all: true
+            disabled_recipes:
+              - Not A Recipe
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let result = PlanConfig::convert(definition, &game_db);
+
+        assert_eq!(
+            result.unwrap_err(),
+            PlanError::UnknownRecipe("Not A Recipe".into(), vec![])
+        );
+    }
+
+    #[test]
+    fn resource_limits_override() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Ingot: 50
+            enabled_recipes:
+              - Iron Ingot
+              - Iron Alloy Ingot
+            resource_limits:
+              Iron Ore: 20
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let config = PlanConfig::convert(definition, &game_db).unwrap();
+
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        assert_eq!(config.find_input(&iron_ore), 20.0);
+        assert_eq!(config.game_db.get_resource_limit(&iron_ore), 20.0);
+
+        let graph = solve(&config).unwrap();
+        let recipes: Vec<&str> = graph
+            .node_weights()
+            .filter_map(|n| match n {
+                SolvedNodeWeight::Production { recipe, .. } => Some(recipe.key.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(recipes, vec!["Recipe_Alternate_IngotIron_C"]);
+    }
+
+    #[test]
+    fn resource_limits_not_a_resource() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Ingot: 50
+            enable_all: true
+            resource_limits:
+              Iron Ingot: 20
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let result = PlanConfig::convert(definition, &game_db);
+
+        assert_eq!(
+            result.unwrap_err(),
+            PlanError::NotAResource("Iron Ingot".into())
+        );
+    }
+
+    #[test]
+    fn input_amount_deserialize() {
+        let yaml = "#
+            - 30
+            - 12.5
+            - unlimited
+            - Unlimited
+        #";
+
+        let result: Vec<InputAmount> = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                InputAmount(30.0),
+                InputAmount(12.5),
+                InputAmount(FloatType::INFINITY),
+                InputAmount(FloatType::INFINITY)
+            ]
+        );
+
+        assert!(serde_yaml::from_str::<InputAmount>("lots").is_err());
+    }
+
+    #[test]
+    fn unlimited_iron_ingot_input() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            inputs:
+              Iron Ingot: unlimited
+            outputs:
+              Iron Plate: 60
+            enabled_recipes:
+              - Iron Ingot
+              - Iron Plate
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let config = PlanConfig::convert(definition, &game_db).unwrap();
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        assert_eq!(config.find_input(&iron_ingot), FloatType::INFINITY);
+
+        let graph = solve(&config).unwrap();
+        let inputs: Vec<&ItemPerMinute> = graph
+            .node_weights()
+            .filter_map(|n| match n {
+                SolvedNodeWeight::Input(input) => Some(input),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(inputs, vec![&ItemPerMinute::new(iron_ingot, 90.0)]);
+    }
+
+    #[test]
+    fn resource_preset_expands_to_resource_limits() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Ingot: 50
+            enable_all: true
+            resource_preset:
+              mk: 1
+              nodes:
+                Iron Ore: { pure: 1 }
+            resource_limits:
+              Copper Ore: 10
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let config = PlanConfig::convert(definition, &game_db).unwrap();
+
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let copper_ore = game_db.find_item("Desc_OreCopper_C").unwrap();
+        let coal = game_db.find_item("Desc_Coal_C").unwrap();
+        assert_eq!(config.find_input(&iron_ore), 120.0);
+        assert_eq!(config.find_input(&copper_ore), 10.0);
+        assert_eq!(config.find_input(&coal), game_db.get_resource_limit(&coal));
+    }
+
+    #[test]
+    fn ambiguous_output_name() {
+        let mut game_db = get_test_game_db();
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut modded_ingot = iron_ingot.as_ref().clone();
+        modded_ingot.key = "Desc_Modded_IronIngot_C".into();
+        game_db.items.push(Rc::new(modded_ingot));
+
+        let yaml = "#
+            outputs:
+              iron ingot: 50
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            PlanConfig::convert(definition, &game_db).unwrap_err(),
+            PlanError::AmbiguousItem(
+                "iron ingot".into(),
+                vec!["Desc_IronIngot_C".into(), "Desc_Modded_IronIngot_C".into()]
+            )
+        );
+
+        let yaml = "#
+            outputs:
+              Desc_Modded_IronIngot_C: 50
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let config = PlanConfig::convert(definition, &game_db).unwrap();
+        assert_eq!(config.outputs[0].item.key, "Desc_Modded_IronIngot_C");
+    }
+
+    #[test]
+    fn builder_iron_ingot() {
+        let game_db = get_test_game_db();
+
+        let config = PlanConfig::builder(&game_db)
+            .output("Iron Ingot", 30.0)
+            .input("Iron Ore", 30.0)
+            .enable_recipe("Recipe_IngotIron_C")
+            .build()
+            .unwrap();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        assert_eq!(config.find_input(&iron_ore), 30.0);
+        let graph = solve(&config).unwrap();
+        assert!(graph.node_weights().any(|n| matches!(
+            n,
+            SolvedNodeWeight::Production { recipe, building_count, .. }
+                if recipe.key == "Recipe_IngotIron_C" && *building_count == 1.0
+        )));
+
+        assert_eq!(
+            PlanConfig::builder(&game_db)
+                .maximize("Iron Ingots")
+                .build()
+                .unwrap_err(),
+            PlanError::UnknownItem("Iron Ingots".into(), vec!["Iron Ingot".into()])
+        );
+    }
+
+    #[test]
+    fn builder_maximize() {
+        let game_db = get_test_game_db();
+
+        let config = PlanConfig::builder(&game_db)
+            .input("Iron Ore", 45.0)
+            .enable_recipe("Recipe_IngotIron_C")
+            .maximize("Iron Ingot")
+            .build()
+            .unwrap();
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        assert_eq!(config.objective, ObjectiveMode::MaximizeOutput);
+        assert_eq!(config.target_output, Some(Rc::clone(&iron_ingot)));
+        assert_eq!(config.find_output(&iron_ingot), 0.0);
+
+        let graph = solve(&config).unwrap();
+        let output = graph
+            .node_weights()
+            .find_map(|n| match n {
+                SolvedNodeWeight::Output(output) => Some(output.amount),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(round(output, 3), 45.0);
+    }
+
+    #[test]
+    fn pinned_recipes_are_enabled() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Plate: 40
+            enabled_recipes:
+              - Iron Plate
+            pinned:
+              Iron Ingot: 2
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let config = PlanConfig::convert(definition, &game_db).unwrap();
+
+        assert_eq!(
+            config.pinned_recipes,
+            HashMap::from([("Recipe_IngotIron_C".to_string(), 2.0)])
+        );
+        assert!(config.game_db.find_recipe("Recipe_IngotIron_C").is_some());
+    }
+
+    #[test]
+    fn output_as_building_count() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Ingot: { recipe: Recipe_IngotIron_C, buildings: 2.5 }
+              Iron Plate: 10
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let config = PlanConfig::convert(definition, &game_db).unwrap();
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        assert_eq!(
+            config.outputs,
+            vec![
+                ItemPerMinute::new(iron_ingot, 75.0),
+                ItemPerMinute::new(iron_plate, 10.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn amounts_per_second() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            time_unit: second
+            outputs:
+              Iron Ingot: 0.5
+              Iron Plate: { recipe: Recipe_IronPlate_C, buildings: 1 }
+            inputs:
+              Iron Ore: 2
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let config = PlanConfig::convert(definition, &game_db).unwrap();
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        assert_eq!(config.time_unit, TimeUnit::Second);
+        assert_eq!(
+            config.outputs,
+            vec![
+                ItemPerMinute::new(iron_ingot, 30.0),
+                ItemPerMinute::new(iron_plate, 20.0)
+            ]
+        );
+        assert_eq!(config.find_input(&iron_ore), 120.0);
+        assert_eq!(config.time_unit.rate_in_unit(90.0), 1.5);
+    }
+
+    #[test]
+    fn producer_limits_cap_gift_trees() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            objective: sink_points
+            outputs:
+              Desc_XmasBall1_C: 0
+            enabled_recipes:
+              - Recipe_XmasBall1_C
+            producer_limits:
+              FICSMAS Gift Tree: 2
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let mut config = PlanConfig::convert(definition, &game_db).unwrap();
+        assert_eq!(
+            config.building_limits,
+            HashMap::from([("Desc_TreeGiftProducer_C".to_string(), 2.0)])
+        );
+
+        let output_amount = |config: &PlanConfig| -> FloatType {
+            solve(config)
+                .unwrap()
+                .node_weights()
+                .filter_map(|n| match n {
+                    SolvedNodeWeight::Output(output) => Some(output.amount),
+                    _ => None,
+                })
+                .sum()
+        };
+
+        // each Gift Tree grows 15 gifts / min
+        assert_eq!(output_amount(&config).round(), 30.0);
+
+        config
+            .building_limits
+            .insert("Desc_TreeGiftProducer_C".to_string(), 1.0);
+        assert_eq!(output_amount(&config).round(), 15.0);
+    }
+
+    #[test]
+    fn output_as_building_count_wrong_recipe() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Plate: { recipe: Recipe_IngotIron_C, buildings: 1 }
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            PlanConfig::convert(definition, &game_db).unwrap_err(),
+            PlanError::NotPrimaryOutput("Iron Ingot".into(), "Iron Plate".into())
+        );
+    }
+
+    #[test]
+    fn unknown_output_item() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Ingots: 50
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let error = PlanConfig::convert(definition, &game_db).unwrap_err();
+        assert_eq!(
+            error,
+            PlanError::UnknownItem("Iron Ingots".into(), vec!["Iron Ingot".into()])
+        );
+        assert_eq!(
+            error.to_string(),
+            "No item exists with the name or key `Iron Ingots`, did you mean Iron Ingot?"
+        );
+    }
+
+    #[test]
+    fn resource_output_not_allowed() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Ore: 30
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(
+            PlanConfig::convert(definition, &game_db).unwrap_err(),
+            PlanError::UnexpectedResource("Iron Ore".into())
+        );
+    }
+
+    #[test]
+    fn resource_output_with_modded_recipe() {
+        let mod_game_db = r#"{
+            "recipes": [
+                {
+                    "key": "Recipe_Modded_OreIron_C",
+                    "name": "Modded Iron Ore",
+                    "inputs": [{"item": "Desc_Stone_C", "amount": 2}],
+                    "outputs": [{"item": "Desc_OreIron_C", "amount": 1}],
+                    "craft_time_secs": 2,
+                    "building": "Desc_ConstructorMk1_C"
+                }
+            ]
+        }"#;
+        let mut mod_path = std::env::temp_dir();
+        mod_path.push(format!(
+            "satisfactory-planner-{}-modded-ore.json",
+            std::process::id()
+        ));
+        std::fs::write(&mod_path, mod_game_db).unwrap();
+
+        let mut game_db_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        game_db_path.push("game-db.json");
+        let game_db = GameDatabase::from_files(&[game_db_path, mod_path.clone()]).unwrap();
+        std::fs::remove_file(mod_path).unwrap();
+
+        let yaml = "#
+            outputs:
+              Iron Ore: 30
+            enabled_recipes:
+              - Modded Iron Ore
+            allow_resource_outputs: true
+            resource_limits:
+              Iron Ore: 0
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let config = PlanConfig::convert(definition, &game_db).unwrap();
+        let graph = solve(&config).unwrap();
+
+        let recipes: Vec<(&str, FloatType)> = graph
+            .node_weights()
+            .filter_map(|n| match n {
+                SolvedNodeWeight::Production {
+                    recipe,
+                    building_count,
+                    ..
+                } => Some((recipe.key.as_str(), *building_count)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(recipes, vec![("Recipe_Modded_OreIron_C", 1.0)]);
+    }
+
+    #[test]
+    fn minimize_resource_requires_target() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Ingot: 30
+            objective: minimize_resource
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(
+            PlanConfig::convert(definition, &game_db).unwrap_err(),
+            PlanError::MissingTargetResource
+        );
+    }
+
+    #[test]
+    fn maximize_output_requires_target() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Ingot: 30
+            objective: maximize_output
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(
+            PlanConfig::convert(definition, &game_db).unwrap_err(),
+            PlanError::MissingTargetOutput
+        );
+    }
+
+    #[test]
+    fn apply_limits() {
+        let game_db = get_test_game_db();
+
+        let yaml = "#
+            outputs:
+              Iron Ingot: 30
+              Copper Ingot: 30
+            enable_all: true
+        #";
+        let definition: PlanConfigDefinition = serde_yaml::from_str(yaml).unwrap();
+        let mut config = PlanConfig::convert(definition, &game_db).unwrap();
+
+        let limits = RequestLimits {
+            solve_timeout: Some(Duration::from_secs(5)),
+            ..Default::default()
+        };
+        assert_eq!(config.apply_limits(&limits), Ok(()));
+        assert_eq!(config.solve_timeout, Some(Duration::from_secs(5)));
+
+        let limits = RequestLimits {
+            max_outputs: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.apply_limits(&limits),
+            Err(PlanError::RequestTooLarge(
+                "2 outputs exceeds the limit of 1".into()
+            ))
+        );
+
+        let limits = RequestLimits {
+            max_recipes: Some(10),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.apply_limits(&limits),
+            Err(PlanError::RequestTooLarge(..))
+        ));
+    }
 }
@@ -0,0 +1,315 @@
+use anyhow::{anyhow, bail};
+use std::{collections::BTreeMap, fmt::Write};
+
+use super::{SolvedGraph, SolvedNodeWeight, TimeUnit};
+use crate::{
+    game::{Building, Fuel},
+    utils::{is_zero, round, FloatType},
+};
+
+const CSV_HEADER: &str = "type,key,building,amount,power_mw";
+
+/// Flattens a solved graph into a CSV shopping list.  Production, producer and generator
+/// rows list the building count in the amount column, input and output rows list items
+/// per `time_unit`.  Generator rows are keyed by the building and its fuel, and list the
+/// power they produce as negative MW.
+pub fn solved_graph_to_csv(graph: &SolvedGraph, time_unit: TimeUnit) -> String {
+    let mut csv = String::new();
+    writeln!(csv, "{}", CSV_HEADER).unwrap();
+
+    for i in graph.node_indices() {
+        match &graph[i] {
+            SolvedNodeWeight::Production {
+                recipe,
+                building_count,
+                power_mw,
+                ..
+            } => {
+                write_row(
+                    &mut csv,
+                    "production",
+                    &recipe.key,
+                    recipe.building.name(),
+                    round(*building_count, 3),
+                    round(*power_mw, 3).to_string(),
+                );
+            }
+            SolvedNodeWeight::Producer(building, building_count) => {
+                write_row(
+                    &mut csv,
+                    "producer",
+                    building.key(),
+                    building.name(),
+                    round(*building_count, 3),
+                    String::new(),
+                );
+            }
+            SolvedNodeWeight::Generator(building, fuel, building_count) => {
+                let power_mw =
+                    building.as_power_generator().power_production_mw as FloatType * building_count;
+                write_row(
+                    &mut csv,
+                    "generator",
+                    &generator_key(building, fuel),
+                    building.name(),
+                    round(*building_count, 3),
+                    round(-power_mw, 3).to_string(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    for i in graph.node_indices() {
+        match &graph[i] {
+            SolvedNodeWeight::Input(input) => {
+                write_row(
+                    &mut csv,
+                    "input",
+                    &input.item.key,
+                    "",
+                    round(time_unit.rate_in_unit(input.amount), 3),
+                    String::new(),
+                );
+            }
+            SolvedNodeWeight::Output(output) => {
+                write_row(
+                    &mut csv,
+                    "output",
+                    &output.item.key,
+                    "",
+                    round(time_unit.rate_in_unit(output.amount), 3),
+                    String::new(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    csv
+}
+
+fn generator_key(building: &Building, fuel: &Fuel) -> String {
+    format!("{}:{}", building.key(), fuel.fuel.item.key)
+}
+
+fn write_row(
+    csv: &mut String,
+    row_type: &str,
+    key: &str,
+    building: &str,
+    amount: FloatType,
+    power: String,
+) {
+    writeln!(
+        csv,
+        "{},{},{},{},{}",
+        row_type,
+        escape(key),
+        escape(building),
+        amount,
+        power
+    )
+    .unwrap();
+}
+
+fn escape(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Compares a solved graph with a CSV previously exported by `solved_graph_to_csv`,
+/// e.g. to check a saved plan against an updated game database.  Returns a warning for
+/// every production, producer or generator row whose building count no longer matches.
+pub fn compare_with_csv(graph: &SolvedGraph, csv: &str) -> anyhow::Result<Vec<String>> {
+    let mut saved_counts = BTreeMap::new();
+    for (line_number, line) in csv.lines().enumerate().skip(1) {
+        let row = split_row(line);
+        if row.len() != 5 {
+            bail!(
+                "Line {}: Expected 5 columns but found {}",
+                line_number + 1,
+                row.len()
+            );
+        }
+
+        if matches!(row[0].as_str(), "production" | "producer" | "generator") {
+            let amount: FloatType = row[3]
+                .parse()
+                .map_err(|_| anyhow!("Line {}: `{}` is not a number", line_number + 1, row[3]))?;
+            saved_counts.insert(row[1].clone(), amount);
+        }
+    }
+
+    let mut current_counts = BTreeMap::new();
+    for weight in graph.node_weights() {
+        match weight {
+            SolvedNodeWeight::Production {
+                recipe,
+                building_count,
+                ..
+            } => {
+                current_counts.insert(recipe.key.clone(), round(*building_count, 3));
+            }
+            SolvedNodeWeight::Producer(building, building_count) => {
+                current_counts.insert(building.key().to_string(), round(*building_count, 3));
+            }
+            SolvedNodeWeight::Generator(building, fuel, building_count) => {
+                current_counts.insert(generator_key(building, fuel), round(*building_count, 3));
+            }
+            _ => {}
+        }
+    }
+
+    let mut warnings = Vec::new();
+    for (key, saved_count) in &saved_counts {
+        match current_counts.get(key) {
+            Some(count) if is_zero(count - saved_count) => {}
+            Some(count) => warnings.push(format!(
+                "{}: {} buildings in the saved plan but {} now",
+                key, saved_count, count
+            )),
+            None => warnings.push(format!("{}: No longer used", key)),
+        }
+    }
+    for (key, count) in &current_counts {
+        if !saved_counts.contains_key(key) {
+            warnings.push(format!("{}: Newly used with {} buildings", key, count));
+        }
+    }
+
+    Ok(warnings)
+}
+
+fn split_row(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{test::get_test_game_db_with_recipes, ItemPerMinute},
+        plan::{solve, PlanConfig},
+    };
+
+    #[test]
+    fn iron_plate_csv() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let csv = solved_graph_to_csv(&graph, config.time_unit);
+        let mut lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.remove(0), CSV_HEADER);
+        lines.sort();
+
+        assert_eq!(
+            lines,
+            vec![
+                "input,Desc_OreIron_C,,60,",
+                "output,Desc_IronPlate_C,,40,",
+                "production,Recipe_IngotIron_C,Smelter,2,8",
+                "production,Recipe_IronPlate_C,Constructor,2,8",
+            ]
+        );
+    }
+
+    #[test]
+    fn iron_plate_csv_per_second() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        config.time_unit = TimeUnit::Second;
+        let graph = solve(&config).unwrap();
+
+        let csv = solved_graph_to_csv(&graph, config.time_unit);
+        let mut lines: Vec<&str> = csv.lines().skip(1).collect();
+        lines.sort();
+
+        assert_eq!(
+            lines,
+            vec![
+                "input,Desc_OreIron_C,,1,",
+                "output,Desc_IronPlate_C,,0.667,",
+                "production,Recipe_IngotIron_C,Smelter,2,8",
+                "production,Recipe_IronPlate_C,Constructor,2,8",
+            ]
+        );
+    }
+
+    #[test]
+    fn compare_with_csv_after_rate_change() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        let graph = solve(&config).unwrap();
+        let csv = solved_graph_to_csv(&graph, config.time_unit);
+        assert_eq!(
+            compare_with_csv(&graph, &csv).unwrap(),
+            Vec::<String>::new()
+        );
+
+        let saved_csv = csv.replace(
+            "production,Recipe_IronPlate_C,Constructor,2,8",
+            "production,Recipe_IronPlate_C,Constructor,1.5,6",
+        );
+        assert_eq!(
+            compare_with_csv(&graph, &saved_csv).unwrap(),
+            vec!["Recipe_IronPlate_C: 1.5 buildings in the saved plan but 2 now"]
+        );
+    }
+
+    #[test]
+    fn generator_csv() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 30.0)], game_db);
+        config.power_output_mw = 75.0;
+        let graph = solve(&config).unwrap();
+
+        let csv = solved_graph_to_csv(&graph, config.time_unit);
+        assert!(csv
+            .lines()
+            .any(|line| line == "generator,Desc_GeneratorCoal_C:Desc_Coal_C,Coal Generator,1,-75"));
+
+        let saved_csv = csv.replace(
+            "generator,Desc_GeneratorCoal_C:Desc_Coal_C,Coal Generator,1,-75",
+            "generator,Desc_GeneratorCoal_C:Desc_Coal_C,Coal Generator,2,-150",
+        );
+        assert_eq!(
+            compare_with_csv(&graph, &saved_csv).unwrap(),
+            vec!["Desc_GeneratorCoal_C:Desc_Coal_C: 2 buildings in the saved plan but 1 now"]
+        );
+    }
+
+    #[test]
+    fn split_row_quoted() {
+        assert_eq!(
+            split_row("production,Recipe_Key,\"Quoted, \"\"Name\"\"\",1,2"),
+            vec!["production", "Recipe_Key", "Quoted, \"Name\"", "1", "2"]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use crate::utils::{is_zero, FloatType};
+
+use super::{NodeWeight, SolvedGraph, SolvedNodeWeight};
+
+/// The differences between two solved plans, e.g. before and after enabling an alternate
+/// recipe.  Nodes are matched by their id and deltas are `after - before`, with
+/// unchanged nodes left out.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlanDiff {
+    pub before_total_buildings: FloatType,
+    pub after_total_buildings: FloatType,
+    pub before_total_inputs: FloatType,
+    pub after_total_inputs: FloatType,
+    pub building_deltas: BTreeMap<String, FloatType>,
+    pub input_deltas: BTreeMap<String, FloatType>,
+}
+
+pub fn diff_plans(before: &SolvedGraph, after: &SolvedGraph) -> PlanDiff {
+    let before_buildings = building_counts(before);
+    let after_buildings = building_counts(after);
+    let before_inputs = input_amounts(before);
+    let after_inputs = input_amounts(after);
+
+    PlanDiff {
+        before_total_buildings: before_buildings.values().sum(),
+        after_total_buildings: after_buildings.values().sum(),
+        before_total_inputs: before_inputs.values().sum(),
+        after_total_inputs: after_inputs.values().sum(),
+        building_deltas: deltas(&before_buildings, &after_buildings),
+        input_deltas: deltas(&before_inputs, &after_inputs),
+    }
+}
+
+fn building_counts(graph: &SolvedGraph) -> BTreeMap<String, FloatType> {
+    graph
+        .node_weights()
+        .filter_map(|n| match n {
+            SolvedNodeWeight::Production { building_count, .. }
+            | SolvedNodeWeight::Producer(_, building_count)
+            | SolvedNodeWeight::Generator(_, _, building_count) => Some((n.id(), *building_count)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn input_amounts(graph: &SolvedGraph) -> BTreeMap<String, FloatType> {
+    graph
+        .node_weights()
+        .filter_map(|n| match n {
+            SolvedNodeWeight::Input(input) => Some((n.id(), input.amount)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn deltas(
+    before: &BTreeMap<String, FloatType>,
+    after: &BTreeMap<String, FloatType>,
+) -> BTreeMap<String, FloatType> {
+    before
+        .keys()
+        .chain(after.keys())
+        .filter_map(|id| {
+            let delta =
+                after.get(id).copied().unwrap_or(0.0) - before.get(id).copied().unwrap_or(0.0);
+            (!is_zero(delta)).then(|| (id.clone(), delta))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        game::{test::get_test_game_db_with_recipes, ItemPerMinute},
+        plan::{solve, PlanConfig},
+    };
+
+    #[test]
+    fn diff_pure_iron_ingot() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronPlate_C",
+            "Recipe_Alternate_PureIronIngot_C",
+        ]);
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let base_game_db = game_db.filter(|r| !r.alternate);
+
+        let before = solve(&PlanConfig::new(
+            vec![ItemPerMinute::new(Rc::clone(&iron_plate), 60.0)],
+            base_game_db,
+        ))
+        .unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 60.0)], game_db);
+        config
+            .inputs
+            .retain(|item, _| item.key == "Desc_OreIron_C" || item.key == "Desc_Water_C");
+        let after = solve(&config).unwrap();
+
+        let diff = diff_plans(&before, &after);
+        assert_eq!(diff.before_total_buildings, 6.0);
+        assert_eq!(diff.before_total_inputs, 90.0);
+        assert_eq!(
+            diff.building_deltas.keys().collect::<Vec<_>>(),
+            vec![
+                "prod:Recipe_Alternate_PureIronIngot_C",
+                "prod:Recipe_IngotIron_C"
+            ]
+        );
+        assert_eq!(diff.building_deltas["prod:Recipe_IngotIron_C"], -3.0);
+        assert!(diff.input_deltas["input:Desc_OreIron_C"] < 0.0);
+        assert!(diff.input_deltas["input:Desc_Water_C"] > 0.0);
+    }
+}
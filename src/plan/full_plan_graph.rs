@@ -1,12 +1,12 @@
-use crate::game::{Building, Item, Recipe};
+use crate::game::{Building, Fuel, Item, Recipe};
 use anyhow::bail;
 use petgraph::{
     stable_graph::{NodeIndex, StableDiGraph},
     Direction::{Incoming, Outgoing},
 };
-use std::{fmt, rc::Rc};
+use std::{collections::HashMap, fmt, rc::Rc, time::Instant};
 
-use super::{NodeWeight, PlanConfig, UNSOLVABLE_PLAN_ERROR};
+use super::{solver::check_deadline, NodeWeight, PlanConfig, PlanError};
 
 pub type FullPlanGraph = StableDiGraph<PlanNodeWeight, Rc<Item>>;
 
@@ -17,6 +17,7 @@ pub enum PlanNodeWeight {
     ByProduct(Rc<Item>),
     Production(Rc<Recipe>, u32),
     Producer(Rc<Building>),
+    Generator(Rc<Building>, Fuel),
 }
 
 impl PlanNodeWeight {
@@ -45,6 +46,11 @@ impl PlanNodeWeight {
         Self::Producer(building)
     }
 
+    #[inline]
+    pub fn new_generator(building: Rc<Building>, fuel: Fuel) -> Self {
+        Self::Generator(building, fuel)
+    }
+
     #[inline]
     pub fn is_input_for_item(&self, item: &Item) -> bool {
         matches!(self, Self::Input(i) if i.as_ref() == item)
@@ -106,6 +112,24 @@ impl NodeWeight for PlanNodeWeight {
     fn is_producer(&self) -> bool {
         matches!(self, Self::Producer(..))
     }
+
+    #[inline]
+    fn is_generator(&self) -> bool {
+        matches!(self, Self::Generator(..))
+    }
+
+    fn id(&self) -> String {
+        match self {
+            Self::Input(item) => format!("input:{}", item.key),
+            Self::Output(item) => format!("output:{}", item.key),
+            Self::ByProduct(item) => format!("byproduct:{}", item.key),
+            Self::Production(recipe, ..) => format!("prod:{}", recipe.key),
+            Self::Producer(building) => format!("producer:{}", building.key()),
+            Self::Generator(building, fuel) => {
+                format!("generator:{}:{}", building.key(), fuel.fuel.item.key)
+            }
+        }
+    }
 }
 
 impl fmt::Display for PlanNodeWeight {
@@ -126,68 +150,190 @@ impl fmt::Display for PlanNodeWeight {
             Self::Producer(building) => {
                 write!(f, "{}", building.name())
             }
+            Self::Generator(building, fuel) => {
+                write!(f, "{}\n{}", building.name(), fuel.fuel.item)
+            }
         }
     }
 }
 
+/// A recipe removed from the full plan graph because some of its inputs couldn't be
+/// supplied
+#[derive(Debug, Clone, PartialEq)]
+pub struct PruneReason {
+    pub recipe: Rc<Recipe>,
+    pub missing_inputs: Vec<Rc<Item>>,
+}
+
 pub fn build_full_plan(config: &PlanConfig) -> Result<FullPlanGraph, anyhow::Error> {
+    build_full_plan_with_prune_reasons(config).map(|(graph, _)| graph)
+}
+
+/// Builds the full plan graph along with the reasons each recipe that was enabled, and
+/// could have been used, was pruned from it
+pub fn build_full_plan_with_prune_reasons(
+    config: &PlanConfig,
+) -> Result<(FullPlanGraph, Vec<PruneReason>), anyhow::Error> {
+    build_full_plan_with_deadline(config, None)
+}
+
+/// Builds the full plan graph, giving up with `RequestTooLarge` once `deadline` has passed
+pub(super) fn build_full_plan_before(
+    config: &PlanConfig,
+    deadline: Option<Instant>,
+) -> Result<FullPlanGraph, anyhow::Error> {
+    build_full_plan_with_deadline(config, deadline).map(|(graph, _)| graph)
+}
+
+/// The deadline is checked between each output's subgraph and each pruning pass, a single
+/// one of those runs to completion
+fn build_full_plan_with_deadline(
+    config: &PlanConfig,
+    deadline: Option<Instant>,
+) -> Result<(FullPlanGraph, Vec<PruneReason>), anyhow::Error> {
     let mut graph = FullPlanGraph::new();
+    let mut depths = ProductionDepths::new();
+    let mut prune_reasons = Vec::new();
 
-    config.outputs.iter().for_each(|o| {
+    for o in &config.outputs {
+        check_deadline(config, deadline)?;
         let idx = graph.add_node(PlanNodeWeight::new_output(Rc::clone(&o.item)));
-        create_children(config, &mut graph, idx, Rc::clone(&o.item));
-    });
+        // resource outputs are only allowed when configured to, and are crafted like any
+        // other item, with the resource input being used when there are no recipes for it
+        if o.item.resource && config.allow_resource_outputs {
+            create_production_by_product(
+                config,
+                &mut graph,
+                &mut depths,
+                idx,
+                Rc::clone(&o.item),
+                0,
+            );
+        } else {
+            create_children(config, &mut graph, &mut depths, idx, Rc::clone(&o.item), 0);
+        }
+    }
+
+    let mut generator_nodes = Vec::new();
+    if config.power_output_mw > 0.0 {
+        for building in config.game_db.find_power_generators() {
+            for fuel in &building.as_power_generator().fuels {
+                generator_nodes.push(create_generator_node(
+                    config,
+                    &mut graph,
+                    &mut depths,
+                    Rc::clone(&building),
+                    fuel.clone(),
+                ));
+            }
+        }
+    }
 
+    // The pruning passes have to run one after another.  Outputs share nodes through the
+    // find_*_node lookups, so a pass over one output can remove nodes another output's
+    // pass would visit, and the Rc based node weights can't be sent across threads.
     for output in &config.outputs {
+        check_deadline(config, deadline)?;
         let idx = find_output_node(&graph, &output.item).unwrap();
         let mut visited = Vec::new();
-        if prune_impossible(config, &mut graph, idx, &mut visited) {
-            bail!("{}", UNSOLVABLE_PLAN_ERROR);
+        prune_impossible(config, &mut graph, idx, &mut visited, &mut prune_reasons);
+    }
+
+    for output in &config.outputs {
+        let supplied = find_output_node(&graph, &output.item)
+            .is_some_and(|idx| graph.edges_directed(idx, Incoming).next().is_some());
+        if supplied {
+            continue;
         }
+
+        // an output with no recipe able to make it, either because none are enabled or
+        // because all of them were pruned for missing inputs, is most likely a mistake in
+        // the enabled recipes, so it gets its own error
+        let all_recipes_pruned = config
+            .game_db
+            .find_recipes_by_output(&output.item)
+            .iter()
+            .all(|recipe| prune_reasons.iter().any(|reason| reason.recipe == *recipe));
+        if all_recipes_pruned
+            && config.game_db.find_item_producers(&output.item).is_empty()
+            && !config.has_input(&output.item)
+        {
+            bail!(PlanError::NoRecipeForOutput(output.item.name.clone()));
+        }
+        bail!(PlanError::UnsolvablePlan);
+    }
+
+    let mut all_generators_pruned = true;
+    for idx in generator_nodes {
+        check_deadline(config, deadline)?;
+        let mut visited = Vec::new();
+        all_generators_pruned &=
+            prune_impossible(config, &mut graph, idx, &mut visited, &mut prune_reasons);
+    }
+
+    if config.power_output_mw > 0.0 && all_generators_pruned {
+        bail!(PlanError::UnsolvablePlan);
     }
 
-    Ok(graph)
+    Ok((graph, prune_reasons))
 }
 
+/// The shallowest depth each production node has been reached at, so that a node first
+/// reached near `max_depth` is expanded again when a shallower path to it turns up
+type ProductionDepths = HashMap<NodeIndex, u32>;
+
 fn create_children(
     config: &PlanConfig,
     graph: &mut FullPlanGraph,
+    depths: &mut ProductionDepths,
     parent_idx: NodeIndex,
     item: Rc<Item>,
+    depth: u32,
 ) -> u32 {
     if item.resource {
         create_input_node(graph, parent_idx, item)
     } else {
-        create_production_by_product(config, graph, parent_idx, item)
+        create_production_by_product(config, graph, depths, parent_idx, item, depth)
     }
 }
 
 fn create_input_node(graph: &mut FullPlanGraph, parent_idx: NodeIndex, item: Rc<Item>) -> u32 {
     let idx = find_input_node(graph, &item)
         .unwrap_or_else(|| graph.add_node(PlanNodeWeight::new_input(Rc::clone(&item))));
-    graph.add_edge(idx, parent_idx, item);
+    graph.update_edge(idx, parent_idx, item);
     0
 }
 
 pub fn create_production_by_product(
     config: &PlanConfig,
     graph: &mut FullPlanGraph,
+    depths: &mut ProductionDepths,
     parent_idx: NodeIndex,
     item: Rc<Item>,
+    depth: u32,
 ) -> u32 {
     let idx = match find_by_product_node(graph, &item) {
         Some(idx) => idx,
         None => graph.add_node(PlanNodeWeight::new_by_product(Rc::clone(&item))),
     };
 
+    // past the maximum depth only inputs and producers, which have no children of their
+    // own, can supply the item
     let mut complexity = u32::MAX;
-    for recipe in config.game_db.find_recipes_by_output(&item) {
+    let recipes = if config.max_depth.is_none_or(|max_depth| depth < max_depth) {
+        config.game_db.find_recipes_by_output(&item)
+    } else {
+        Vec::new()
+    };
+    for recipe in recipes {
         complexity = complexity.min(create_production_node(
             config,
             graph,
+            depths,
             idx,
             recipe,
             Rc::clone(&item),
+            depth,
         ));
     }
 
@@ -209,6 +355,27 @@ pub fn create_production_by_product(
     complexity
 }
 
+fn create_generator_node(
+    config: &PlanConfig,
+    graph: &mut FullPlanGraph,
+    depths: &mut ProductionDepths,
+    building: Rc<Building>,
+    fuel: Fuel,
+) -> NodeIndex {
+    let idx = graph.add_node(PlanNodeWeight::new_generator(building, fuel.clone()));
+
+    create_children(config, graph, depths, idx, Rc::clone(&fuel.fuel.item), 0);
+    if let Some(supplemental) = &fuel.supplemental {
+        create_children(config, graph, depths, idx, Rc::clone(&supplemental.item), 0);
+    }
+
+    if let Some(by_product) = &fuel.by_product {
+        create_partial_by_product_node(graph, idx, Rc::clone(&by_product.item));
+    }
+
+    idx
+}
+
 fn create_producer_node(
     _config: &PlanConfig,
     graph: &mut FullPlanGraph,
@@ -218,42 +385,60 @@ fn create_producer_node(
 ) -> u32 {
     let idx = find_producer_node(graph, &building)
         .unwrap_or_else(|| graph.add_node(PlanNodeWeight::new_producer(building)));
-    graph.add_edge(idx, parent_idx, item);
+    graph.update_edge(idx, parent_idx, item);
     1
 }
 
 fn create_production_node(
     config: &PlanConfig,
     graph: &mut FullPlanGraph,
+    depths: &mut ProductionDepths,
     parent_idx: NodeIndex,
     recipe: Rc<Recipe>,
     item: Rc<Item>,
+    depth: u32,
 ) -> u32 {
-    if let Some(existing_idx) = find_production_node(graph, &recipe) {
-        if let PlanNodeWeight::Production(_, complexity) = &graph[existing_idx] {
-            *complexity
-        } else {
-            0
+    let idx = match find_production_node(graph, &recipe) {
+        // without a max_depth the node's inputs were already fully expanded, and with one
+        // they only need expanding again when this path reaches the node at a shallower
+        // depth, where more of its input chain fits
+        Some(existing_idx) if config.max_depth.is_none() || depths[&existing_idx] <= depth => {
+            if let PlanNodeWeight::Production(_, complexity) = &graph[existing_idx] {
+                return *complexity;
+            } else {
+                return 0;
+            }
         }
-    } else {
-        let idx = graph.add_node(PlanNodeWeight::new_production(Rc::clone(&recipe)));
+        Some(existing_idx) => existing_idx,
+        None => {
+            let idx = graph.add_node(PlanNodeWeight::new_production(Rc::clone(&recipe)));
 
-        for output in &recipe.outputs {
-            if output.item != item {
-                create_partial_by_product_node(graph, idx, Rc::clone(&output.item));
+            for output in &recipe.outputs {
+                if output.item != item {
+                    create_partial_by_product_node(graph, idx, Rc::clone(&output.item));
+                }
             }
+            idx
         }
+    };
+    depths.insert(idx, depth);
 
-        let mut complexity = 0;
-        for input in &recipe.inputs {
-            complexity =
-                complexity.max(create_children(config, graph, idx, Rc::clone(&input.item)));
-        }
-        complexity += 1;
-        graph[idx].set_complexity(complexity);
-        graph.add_edge(idx, parent_idx, item);
-        complexity
+    let mut complexity = 0;
+    for input in &recipe.inputs {
+        complexity = complexity.max(create_children(
+            config,
+            graph,
+            depths,
+            idx,
+            Rc::clone(&input.item),
+            depth + 1,
+        ));
     }
+    // an input that can't be supplied has a complexity of u32::MAX
+    complexity = complexity.saturating_add(1);
+    graph[idx].set_complexity(complexity);
+    graph.update_edge(idx, parent_idx, item);
+    complexity
 }
 
 fn create_partial_by_product_node(
@@ -274,6 +459,7 @@ fn prune_impossible(
     graph: &mut FullPlanGraph,
     idx: NodeIndex,
     visited: &mut Vec<NodeIndex>,
+    prune_reasons: &mut Vec<PruneReason>,
 ) -> bool {
     if visited.contains(&idx) {
         return false;
@@ -285,7 +471,7 @@ fn prune_impossible(
             let mut child_walker = graph.neighbors_directed(idx, Incoming).detach();
             let mut all_deleted = true;
             while let Some(child_idx) = child_walker.next_node(graph) {
-                all_deleted &= prune_impossible(config, graph, child_idx, visited);
+                all_deleted &= prune_impossible(config, graph, child_idx, visited, prune_reasons);
             }
 
             if all_deleted {
@@ -294,16 +480,33 @@ fn prune_impossible(
             all_deleted
         }
         PlanNodeWeight::Production(recipe, ..) => {
+            let recipe = Rc::clone(recipe);
             let total_inputs = recipe.inputs.len();
             let mut child_walker = graph.neighbors_directed(idx, Incoming).detach();
             let mut total_children = 0;
             while let Some(child_idx) = child_walker.next_node(graph) {
-                if !prune_impossible(config, graph, child_idx, visited) {
+                if !prune_impossible(config, graph, child_idx, visited, prune_reasons) {
                     total_children += 1;
                 }
             }
 
             if total_children != total_inputs {
+                let missing_inputs = recipe
+                    .inputs
+                    .iter()
+                    .map(|input| &input.item)
+                    .filter(|item| {
+                        !graph
+                            .edges_directed(idx, Incoming)
+                            .any(|edge| edge.weight() == *item)
+                    })
+                    .cloned()
+                    .collect();
+                prune_reasons.push(PruneReason {
+                    recipe,
+                    missing_inputs,
+                });
+
                 prune(graph, idx);
                 true
             } else {
@@ -320,7 +523,7 @@ fn prune_impossible(
         }
         PlanNodeWeight::Output(..) => {
             if let Some(child_idx) = graph.neighbors_directed(idx, Incoming).next() {
-                if prune_impossible(config, graph, child_idx, visited) {
+                if prune_impossible(config, graph, child_idx, visited, prune_reasons) {
                     graph.remove_node(idx);
                     true
                 } else {
@@ -331,6 +534,31 @@ fn prune_impossible(
                 true
             }
         }
+        PlanNodeWeight::Generator(_, fuel) => {
+            let total_inputs = 1 + fuel.supplemental.iter().count();
+            let mut child_walker = graph.neighbors_directed(idx, Incoming).detach();
+            let mut total_children = 0;
+            while let Some(child_idx) = child_walker.next_node(graph) {
+                if !prune_impossible(config, graph, child_idx, visited, prune_reasons) {
+                    total_children += 1;
+                }
+            }
+
+            if total_children != total_inputs {
+                // only remove the generator itself, its fuel sources may be shared with
+                // other generators or production nodes
+                let mut parent_walker = graph.neighbors_directed(idx, Outgoing).detach();
+                while let Some(parent_idx) = parent_walker.next_node(graph) {
+                    if graph.neighbors_undirected(parent_idx).count() == 1 {
+                        graph.remove_node(parent_idx);
+                    }
+                }
+                graph.remove_node(idx);
+                true
+            } else {
+                false
+            }
+        }
         PlanNodeWeight::Producer(..) => false,
     }
 }
@@ -388,3 +616,96 @@ fn find_by_product_node(graph: &FullPlanGraph, item: &Item) -> Option<NodeIndex>
         .node_indices()
         .find(|i| graph[*i].is_by_product_for_item(item))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{test::get_test_game_db_with_recipes, ItemPerMinute};
+    use std::time::Duration;
+
+    #[test]
+    fn prune_reason_for_missing_plastic() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronPlate_C",
+            "Recipe_Alternate_CoatedIronPlate_C",
+        ]);
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let plastic = game_db.find_item("Desc_Plastic_C").unwrap();
+        let coated_iron_plate = game_db
+            .find_recipe("Recipe_Alternate_CoatedIronPlate_C")
+            .unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+
+        let (_, prune_reasons) = build_full_plan_with_prune_reasons(&config).unwrap();
+        assert_eq!(
+            prune_reasons,
+            vec![PruneReason {
+                recipe: coated_iron_plate,
+                missing_inputs: vec![plastic],
+            }]
+        );
+    }
+
+    #[test]
+    fn all_output_recipes_pruned() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IronPlate_C"]);
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+
+        // the plate recipe is enabled but nothing makes its iron ingots
+        let error = build_full_plan(&config).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PlanError>(),
+            Some(&PlanError::NoRecipeForOutput("Iron Plate".into()))
+        );
+    }
+
+    #[test]
+    fn max_depth_graph_independent_of_output_order() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronRod_C",
+            "Recipe_Screw_C",
+        ]);
+        let screw = game_db.find_item("Desc_IronScrew_C").unwrap();
+        let iron_rod = game_db.find_item("Desc_IronRod_C").unwrap();
+
+        let node_ids = |outputs: Vec<ItemPerMinute>| {
+            let mut config = PlanConfig::new(outputs, game_db.clone());
+            config.max_depth = Some(2);
+            let graph = build_full_plan(&config).unwrap();
+            let mut ids: Vec<String> = graph.node_weights().map(|n| n.id()).collect();
+            ids.sort();
+            ids
+        };
+
+        // screws reach the rod recipe a step deeper than the rod output does, so when
+        // they come first the rod recipe has to be expanded again for the rod output
+        let screws_first = node_ids(vec![
+            ItemPerMinute::new(Rc::clone(&screw), 40.0),
+            ItemPerMinute::new(Rc::clone(&iron_rod), 15.0),
+        ]);
+        let rods_first = node_ids(vec![
+            ItemPerMinute::new(iron_rod, 15.0),
+            ItemPerMinute::new(screw, 40.0),
+        ]);
+        assert_eq!(screws_first, rods_first);
+        assert!(screws_first.contains(&"prod:Recipe_IngotIron_C".to_string()));
+    }
+
+    #[test]
+    fn graph_building_stops_at_deadline() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        config.solve_timeout = Some(Duration::ZERO);
+
+        let error = build_full_plan_before(&config, Some(Instant::now())).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PlanError>(),
+            Some(PlanError::RequestTooLarge(..))
+        ));
+        assert!(build_full_plan_before(&config, None).is_ok());
+    }
+}
@@ -0,0 +1,129 @@
+use petgraph::stable_graph::EdgeIndex;
+use std::collections::HashMap;
+
+use super::SolvedGraph;
+use crate::{
+    game::logistics::{conveyor_throughput, BeltTier, PipeTier},
+    utils::{FloatType, EPSILON},
+};
+
+/// The belts needed to carry an edge's items.  Fluids are carried by pipelines instead,
+/// which are counted the same way.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EdgeLogistics {
+    pub belt_count: u32,
+    pub belt_utilization: FloatType,
+    /// Whether even a single belt, or pipeline, of the highest tier is too slow for the
+    /// edge, so it has to be split whatever tier is used
+    pub exceeds_single_belt: bool,
+}
+
+pub fn edge_logistics(
+    graph: &SolvedGraph,
+    belt_tier: BeltTier,
+    pipe_tier: PipeTier,
+) -> HashMap<EdgeIndex, EdgeLogistics> {
+    graph
+        .edge_indices()
+        .map(|e| {
+            let edge = &graph[e];
+            let throughput = conveyor_throughput(edge.item.state, belt_tier, pipe_tier);
+            let belt_count = (edge.amount / throughput).ceil() as u32;
+            let highest_throughput =
+                conveyor_throughput(edge.item.state, BeltTier::HIGHEST, PipeTier::HIGHEST);
+
+            let logistics = EdgeLogistics {
+                belt_count,
+                belt_utilization: edge.amount / (belt_count as FloatType * throughput),
+                exceeds_single_belt: edge.amount > highest_throughput + EPSILON,
+            };
+            (e, logistics)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{test::get_test_game_db_with_recipes, ItemPerMinute},
+        plan::{solve, PlanConfig},
+    };
+
+    fn find_edge_logistics(
+        graph: &SolvedGraph,
+        logistics: &HashMap<EdgeIndex, EdgeLogistics>,
+        item_key: &str,
+    ) -> EdgeLogistics {
+        let e = graph
+            .edge_indices()
+            .find(|e| graph[*e].item.key == item_key)
+            .unwrap();
+        logistics[&e]
+    }
+
+    #[test]
+    fn belts_for_iron_plates() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 80.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let logistics = edge_logistics(&graph, BeltTier::Mk1, PipeTier::Mk1);
+        assert_eq!(
+            find_edge_logistics(&graph, &logistics, "Desc_OreIron_C"),
+            EdgeLogistics {
+                belt_count: 2,
+                belt_utilization: 1.0,
+                exceeds_single_belt: false
+            }
+        );
+        assert_eq!(
+            find_edge_logistics(&graph, &logistics, "Desc_IronPlate_C"),
+            EdgeLogistics {
+                belt_count: 2,
+                belt_utilization: 80.0 / 120.0,
+                exceeds_single_belt: false
+            }
+        );
+    }
+
+    #[test]
+    fn pipes_for_fluids() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_LiquidFuel_C"]);
+
+        let fuel = game_db.find_item("Desc_LiquidFuel_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(fuel, 40.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let logistics = edge_logistics(&graph, BeltTier::Mk1, PipeTier::Mk1);
+        assert_eq!(
+            find_edge_logistics(&graph, &logistics, "Desc_LiquidOil_C"),
+            EdgeLogistics {
+                belt_count: 1,
+                belt_utilization: 0.2,
+                exceeds_single_belt: false
+            }
+        );
+    }
+
+    #[test]
+    fn iron_ore_exceeds_single_belt() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 1500.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let logistics = edge_logistics(&graph, BeltTier::Mk6, PipeTier::Mk2);
+        assert_eq!(
+            find_edge_logistics(&graph, &logistics, "Desc_OreIron_C"),
+            EdgeLogistics {
+                belt_count: 2,
+                belt_utilization: 0.625,
+                exceeds_single_belt: true
+            }
+        );
+    }
+}
@@ -1,18 +1,79 @@
 use petgraph::dot::Dot;
 use petgraph::stable_graph::StableDiGraph;
 use std::fmt;
+use thiserror::Error;
 
+use crate::game::GameDatabaseError;
+
+mod alternates;
+mod cache;
 mod config;
+mod csv;
+mod diff;
 mod full_plan_graph;
+mod logistics;
 mod solved_graph;
 mod solver;
+mod summary;
 
+pub use alternates::*;
+pub use cache::*;
 pub use config::*;
+pub use csv::*;
+pub use diff::*;
 pub use full_plan_graph::*;
+pub use logistics::*;
 pub use solved_graph::*;
 pub use solver::*;
+pub use summary::*;
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum PlanError {
+    #[error("No recipe exists with the name or key `{0}`{}", did_you_mean(.1))]
+    UnknownRecipe(String, Vec<String>),
+    #[error("No item exists with the name or key `{0}`{}", did_you_mean(.1))]
+    UnknownItem(String, Vec<String>),
+    #[error("No building exists with the name or key `{0}`")]
+    UnknownBuilding(String),
+    #[error(
+        "More than one item is named `{0}`, use one of these keys instead: {}",
+        .1.join(", ")
+    )]
+    AmbiguousItem(String, Vec<String>),
+    #[error("The resource `{0}` is not allowed in outputs.")]
+    UnexpectedResource(String),
+    #[error("The recipe `{0}` does not have `{1}` as its primary output.")]
+    NotPrimaryOutput(String, String),
+    #[error("The item `{0}` is not a resource and can't appear in resource_limits.")]
+    NotAResource(String),
+    #[error("The minimize_resource objective requires a target_resource.")]
+    MissingTargetResource,
+    #[error("The maximize_output objective requires a target_output.")]
+    MissingTargetOutput,
+    #[error("Invalid resource_preset: {0}")]
+    InvalidResourcePreset(GameDatabaseError),
+    #[error(
+        "Unable to solve the given factory plan. Raising the limit of any of these resources would make it solvable: {}",
+        .0.join(", ")
+    )]
+    InsufficientResources(Vec<String>),
+    #[error("Unable to solve the given factory plan.")]
+    UnsolvablePlan,
+    #[error("The plan's outputs can grow without limit, its inputs need limits when maximizing sink points or an output.")]
+    UnboundedPlan,
+    #[error("None of the enabled recipes can produce the output `{0}` from the available inputs.")]
+    NoRecipeForOutput(String),
+    #[error("The plan is too large: {0}")]
+    RequestTooLarge(String),
+}
 
-pub const UNSOLVABLE_PLAN_ERROR: &str = "Unable to solve the given factory plan.";
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(", did you mean {}?", suggestions.join(" or "))
+    }
+}
 
 pub trait NodeWeight
 where
@@ -23,35 +84,102 @@ where
     fn is_output(&self) -> bool;
     fn is_by_product(&self) -> bool;
     fn is_production(&self) -> bool;
+    #[allow(dead_code)]
     fn is_producer(&self) -> bool;
+    fn is_generator(&self) -> bool;
+    /// An identifier derived from what the node is rather than where it is in the graph,
+    /// so that it stays the same when a plan is re-solved
+    fn id(&self) -> String;
 }
 
 pub fn print_graph<N: NodeWeight, E: fmt::Display>(graph: &StableDiGraph<N, E>) {
-    println!(
-        "{}",
-        format!(
-            "{}",
-            Dot::with_attr_getters(&graph, &[], &|_, _| String::new(), &|_, n| {
-                let color = if n.1.is_input_resource() {
-                    "lightslategray"
-                } else if n.1.is_input() {
-                    "peru"
-                } else if n.1.is_output() {
-                    "mediumseagreen"
-                } else if n.1.is_by_product() {
-                    "cornflowerblue"
-                } else if n.1.is_production() {
-                    "darkorange"
-                } else {
-                    "white"
-                };
-
-                format!(
-                    "style=\"solid,filled\" shape=\"box\" fontcolor=\"white\" color=\"{}\"",
-                    color
-                )
-            })
-        )
-        .replace("\\l", "\\n")
+    println!("{}", graph_to_dot(graph));
+}
+
+/// Converts a solved graph to DOT with every rate given in `time_unit`
+pub fn solved_graph_to_dot(graph: &SolvedGraph, time_unit: TimeUnit) -> String {
+    let labelled = graph.map(
+        |_, node| LabelledNode {
+            node,
+            label: node.label(time_unit),
+        },
+        |_, edge| rate_label(edge, time_unit),
     );
+    graph_to_dot(&labelled)
+}
+
+/// A node that is displayed with a precomputed label
+struct LabelledNode<'a, N> {
+    node: &'a N,
+    label: String,
+}
+
+impl<N> fmt::Display for LabelledNode<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label)
+    }
+}
+
+impl<N: NodeWeight> NodeWeight for LabelledNode<'_, N> {
+    fn is_input(&self) -> bool {
+        self.node.is_input()
+    }
+
+    fn is_input_resource(&self) -> bool {
+        self.node.is_input_resource()
+    }
+
+    fn is_output(&self) -> bool {
+        self.node.is_output()
+    }
+
+    fn is_by_product(&self) -> bool {
+        self.node.is_by_product()
+    }
+
+    fn is_production(&self) -> bool {
+        self.node.is_production()
+    }
+
+    fn is_producer(&self) -> bool {
+        self.node.is_producer()
+    }
+
+    fn is_generator(&self) -> bool {
+        self.node.is_generator()
+    }
+
+    fn id(&self) -> String {
+        self.node.id()
+    }
+}
+
+pub fn graph_to_dot<N: NodeWeight, E: fmt::Display>(graph: &StableDiGraph<N, E>) -> String {
+    format!(
+        "{}",
+        Dot::with_attr_getters(&graph, &[], &|_, _| String::new(), &|_, n| {
+            let color = if n.1.is_input_resource() {
+                "lightslategray"
+            } else if n.1.is_input() {
+                "peru"
+            } else if n.1.is_output() {
+                "mediumseagreen"
+            } else if n.1.is_by_product() {
+                "cornflowerblue"
+            } else if n.1.is_production() {
+                "darkorange"
+            } else if n.1.is_generator() {
+                "goldenrod"
+            } else {
+                "white"
+            };
+
+            format!(
+                "id=\"{}\" style=\"solid,filled\" shape=\"box\" fontcolor=\"white\" color=\"{}\"",
+                n.1.id(),
+                color
+            )
+        })
+    )
+    .replace("\\l", "\\n")
 }
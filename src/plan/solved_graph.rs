@@ -1,13 +1,14 @@
 use super::{
     full_plan_graph::{FullPlanGraph, PlanNodeWeight},
-    NodeWeight,
+    NodeWeight, TimeUnit,
 };
 use crate::{
-    game::{Building, Item, ItemPerMinute, Recipe},
-    utils::{clamp_to_zero, is_zero, round, FloatType},
+    game::{Building, Fuel, Item, ItemPerMinute, PowerConsumption, Recipe, RecipePower},
+    utils::{clamp_to_zero_within, is_zero, is_zero_within, round, FloatType, EPSILON},
 };
 use good_lp::{Solution, Variable};
 use petgraph::{
+    algo::{astar, has_path_connecting, tarjan_scc, toposort},
     stable_graph::{EdgeIndex, NodeIndex, StableDiGraph},
     visit::EdgeRef,
     Direction::{Incoming, Outgoing},
@@ -21,8 +22,18 @@ pub enum SolvedNodeWeight {
     Input(ItemPerMinute),
     Output(ItemPerMinute),
     ByProduct(ItemPerMinute),
-    Production(Rc<Recipe>, FloatType),
+    Production {
+        recipe: Rc<Recipe>,
+        building_count: FloatType,
+        clock_speed: FloatType,
+        /// The average power usage of all the buildings combined
+        power_mw: FloatType,
+        /// The range the power usage swings between, for buildings with variable power
+        /// usage
+        power_range: Option<RecipePower>,
+    },
     Producer(Rc<Building>, FloatType),
+    Generator(Rc<Building>, Fuel, FloatType),
 }
 
 impl SolvedNodeWeight {
@@ -43,13 +54,49 @@ impl SolvedNodeWeight {
 
     #[inline]
     pub fn new_production(recipe: Rc<Recipe>, building_count: FloatType) -> Self {
-        Self::Production(recipe, building_count)
+        Self::new_production_with_clock_speed(recipe, building_count, 100.0)
+    }
+
+    #[inline]
+    pub fn new_production_with_clock_speed(
+        recipe: Rc<Recipe>,
+        building_count: FloatType,
+        clock_speed: FloatType,
+    ) -> Self {
+        let power_mw = production_power_mw(&recipe, building_count, clock_speed);
+        let power_range = production_power_range(&recipe, building_count, clock_speed);
+        Self::Production {
+            recipe,
+            building_count,
+            clock_speed,
+            power_mw,
+            power_range,
+        }
     }
 
     #[inline]
     pub fn new_producer(recipe: Rc<Building>, building_count: FloatType) -> Self {
         Self::Producer(recipe, building_count)
     }
+
+    #[inline]
+    pub fn new_generator(building: Rc<Building>, fuel: Fuel, building_count: FloatType) -> Self {
+        Self::Generator(building, fuel, building_count)
+    }
+
+    /// How many times a minute all of a production node's buildings together complete
+    /// their recipe, taking the clock speed into account
+    pub fn crafts_per_min(&self) -> Option<FloatType> {
+        match self {
+            Self::Production {
+                recipe,
+                building_count,
+                clock_speed,
+                ..
+            } => Some(building_count * clock_speed / 100.0 * 60.0 / recipe.craft_time_secs),
+            _ => None,
+        }
+    }
 }
 
 impl NodeWeight for SolvedNodeWeight {
@@ -75,53 +122,105 @@ impl NodeWeight for SolvedNodeWeight {
 
     #[inline]
     fn is_production(&self) -> bool {
-        matches!(self, Self::Production(..))
+        matches!(self, Self::Production { .. })
     }
 
     #[inline]
     fn is_producer(&self) -> bool {
         matches!(self, Self::Producer(..))
     }
+
+    #[inline]
+    fn is_generator(&self) -> bool {
+        matches!(self, Self::Generator(..))
+    }
+
+    fn id(&self) -> String {
+        match self {
+            Self::Input(input) => format!("input:{}", input.item.key),
+            Self::Output(output) => format!("output:{}", output.item.key),
+            Self::ByProduct(by_product) => format!("byproduct:{}", by_product.item.key),
+            Self::Production { recipe, .. } => format!("prod:{}", recipe.key),
+            Self::Producer(building, ..) => format!("producer:{}", building.key()),
+            Self::Generator(building, fuel, ..) => {
+                format!("generator:{}:{}", building.key(), fuel.fuel.item.key)
+            }
+        }
+    }
 }
 
-impl fmt::Display for SolvedNodeWeight {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl SolvedNodeWeight {
+    /// The node's label with its rates given in `time_unit`
+    pub fn label(&self, time_unit: TimeUnit) -> String {
         match self {
-            Self::Input(input) => {
-                write!(f, "{}\n{} / min", input.item, round(input.amount, 3))
+            Self::Input(input) | Self::ByProduct(input) | Self::Output(input) => {
+                rate_label(input, time_unit)
             }
-            Self::Production(recipe, building_count) => {
-                write!(
-                    f,
+            Self::Production {
+                recipe,
+                building_count,
+                clock_speed,
+                power_range,
+                ..
+            } => {
+                let mut label = format!(
                     "{}\n{}x {}",
                     recipe,
                     round(*building_count, 3),
                     recipe.building
-                )
-            }
-            Self::ByProduct(by_product, ..) => {
-                write!(
-                    f,
-                    "{}\n{} / min",
-                    by_product.item,
-                    round(by_product.amount, 3)
-                )
-            }
-            Self::Output(output, ..) => {
-                write!(f, "{}\n{} / min", output.item, round(output.amount, 3))
+                );
+
+                if !is_zero(*clock_speed - 100.0) {
+                    label.push_str(&format!(" @ {}%", round(*clock_speed, 3)));
+                }
+                label.push_str(&format!(
+                    "\n{} crafts / {}, {} s each",
+                    round(time_unit.rate_in_unit(self.crafts_per_min().unwrap()), 3),
+                    time_unit.abbreviation(),
+                    round(recipe.craft_time_secs, 3)
+                ));
+                if let Some(power_range) = power_range {
+                    label.push_str(&format!(
+                        "\n{} - {} MW",
+                        round(power_range.min_mw, 3),
+                        round(power_range.max_mw, 3)
+                    ));
+                }
+                label
             }
             Self::Producer(building, building_count) => {
-                write!(f, "{}x {}", round(*building_count, 3), building)
+                format!("{}x {}", round(*building_count, 3), building)
+            }
+            Self::Generator(building, fuel, building_count) => {
+                format!(
+                    "{}\n{}x {}",
+                    fuel.fuel.item,
+                    round(*building_count, 3),
+                    building
+                )
             }
         }
     }
 }
 
+impl fmt::Display for SolvedNodeWeight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label(TimeUnit::Minute))
+    }
+}
+
+/// The label of an item and its rate, with the rate given in `time_unit`
+pub fn rate_label(value: &ItemPerMinute, time_unit: TimeUnit) -> String {
+    format!("{}\n{}", value.item, time_unit.format_rate(value.amount))
+}
+
 pub fn copy_solution<S: Solution>(
     full_graph: &FullPlanGraph,
     solution: S,
     node_variables: HashMap<NodeIndex, Variable>,
     edge_variables: HashMap<EdgeIndex, Variable>,
+    epsilon: FloatType,
+    keep_unused: bool,
 ) -> SolvedGraph {
     let mut node_mapping: HashMap<NodeIndex, NodeIndex> = HashMap::new();
 
@@ -129,9 +228,12 @@ pub fn copy_solution<S: Solution>(
 
     for i in full_graph.node_indices() {
         let var = *node_variables.get(&i).unwrap();
-        let solution = solution.value(var);
+        let solution = clamp_to_zero_within(solution.value(var), epsilon);
 
-        if is_zero(solution) {
+        // unused production nodes are kept with no buildings when asked to, so that they
+        // can be shown as available
+        let keep = keep_unused && full_graph[i].is_production();
+        if solution == 0.0 && !keep {
             continue;
         }
 
@@ -151,6 +253,9 @@ pub fn copy_solution<S: Solution>(
             PlanNodeWeight::Producer(building) => solved_graph.add_node(
                 SolvedNodeWeight::new_producer(Rc::clone(building), solution),
             ),
+            PlanNodeWeight::Generator(building, fuel) => solved_graph.add_node(
+                SolvedNodeWeight::new_generator(Rc::clone(building), fuel.clone(), solution),
+            ),
         };
 
         node_mapping.insert(i, new_idx);
@@ -160,23 +265,188 @@ pub fn copy_solution<S: Solution>(
         let var = *edge_variables.get(&e).unwrap();
         let solution = solution.value(var);
 
-        if is_zero(solution) {
+        if is_zero_within(solution, epsilon) {
             continue;
         }
 
+        // an edge can be just above zero while a node it connects is just below, e.g. a
+        // recipe with a large output amount, in which case the edge is dropped as well
         let (source, target) = full_graph.edge_endpoints(e).unwrap();
-        let new_source = *node_mapping.get(&source).unwrap();
-        let new_target = *node_mapping.get(&target).unwrap();
+        let (Some(new_source), Some(new_target)) =
+            (node_mapping.get(&source), node_mapping.get(&target))
+        else {
+            continue;
+        };
 
         let weight = ItemPerMinute::new(Rc::clone(&full_graph[e]), solution);
-        solved_graph.add_edge(new_source, new_target, weight);
+        solved_graph.add_edge(*new_source, *new_target, weight);
+    }
+
+    recompute_production_rates(&mut solved_graph);
+    cleanup_by_product_nodes(&mut solved_graph, epsilon);
+    coalesce_parallel_edges(&mut solved_graph);
+    sort_topologically(solved_graph)
+}
+
+/// Replaces the solver's rates on edges into and out of production nodes with ones
+/// computed from the building count and the recipe's per craft amounts, which avoids
+/// drift for recipes whose craft times don't evenly divide a minute.  Edges that share
+/// an item with another edge of the same node are left alone, as the solver decided how
+/// it is split between them.
+fn recompute_production_rates(graph: &mut SolvedGraph) {
+    let production_nodes: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|i| graph[*i].is_production())
+        .collect();
+
+    for i in production_nodes {
+        let (recipe, building_count) = match &graph[i] {
+            SolvedNodeWeight::Production {
+                recipe,
+                building_count,
+                ..
+            } => (Rc::clone(recipe), *building_count),
+            _ => unreachable!(),
+        };
+
+        for direction in [Incoming, Outgoing] {
+            let edges: Vec<EdgeIndex> =
+                graph.edges_directed(i, direction).map(|e| e.id()).collect();
+            for e in &edges {
+                let item = Rc::clone(&graph[*e].item);
+                if edges.iter().filter(|o| graph[**o].item == item).count() > 1 {
+                    continue;
+                }
+
+                let rate = match direction {
+                    Incoming => recipe.input_rate(&item, building_count),
+                    Outgoing => recipe.output_rate(&item, building_count),
+                };
+                if let Some(rate) = rate {
+                    graph[*e].amount = rate;
+                }
+            }
+        }
     }
+}
+
+/// Rebuilds the graph with its nodes in topological order so that inputs come first and
+/// outputs last.  Graphs with cycles are returned unchanged.
+fn sort_topologically(graph: SolvedGraph) -> SolvedGraph {
+    let order = match toposort(&graph, None) {
+        Ok(order) => order,
+        Err(..) => return graph,
+    };
 
-    cleanup_by_product_nodes(&mut solved_graph);
-    solved_graph
+    let mut node_mapping: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+    let mut sorted_graph = SolvedGraph::with_capacity(graph.node_count(), graph.edge_count());
+
+    for i in &order {
+        node_mapping.insert(*i, sorted_graph.add_node(graph[*i].clone()));
+    }
+
+    for i in &order {
+        for edge in graph.edges_directed(*i, Outgoing) {
+            sorted_graph.add_edge(
+                node_mapping[&edge.source()],
+                node_mapping[&edge.target()],
+                edge.weight().clone(),
+            );
+        }
+    }
+
+    sorted_graph
+}
+
+pub fn round_to_whole_buildings(graph: &mut SolvedGraph) {
+    for weight in graph.node_weights_mut() {
+        if let SolvedNodeWeight::Production {
+            recipe,
+            building_count,
+            clock_speed,
+            power_mw,
+            power_range,
+        } = weight
+        {
+            if is_zero(*building_count) {
+                continue;
+            }
+            let whole_building_count = (*building_count - EPSILON).ceil().max(1.0);
+            *clock_speed = *building_count / whole_building_count * 100.0;
+            *building_count = whole_building_count;
+            *power_mw = production_power_mw(recipe, *building_count, *clock_speed);
+            *power_range = production_power_range(recipe, *building_count, *clock_speed);
+        }
+    }
 }
 
-fn cleanup_by_product_nodes(graph: &mut SolvedGraph) {
+/// Rounds the item amounts of every node and edge to the given number of decimals
+pub fn round_amounts(graph: &mut SolvedGraph, decimals: u8) {
+    for weight in graph.node_weights_mut() {
+        match weight {
+            SolvedNodeWeight::Input(item)
+            | SolvedNodeWeight::Output(item)
+            | SolvedNodeWeight::ByProduct(item) => item.amount = round(item.amount, decimals),
+            _ => {}
+        }
+    }
+
+    for weight in graph.edge_weights_mut() {
+        weight.amount = round(weight.amount, decimals);
+    }
+}
+
+fn production_power_mw(
+    recipe: &Recipe,
+    building_count: FloatType,
+    clock_speed: FloatType,
+) -> FloatType {
+    // every building runs at the node's clock speed except the last, which is
+    // underclocked to cover the fractional remainder
+    recipe.average_mw(clock_speed) * building_count.floor()
+        + recipe.average_mw(building_count.fract() * clock_speed)
+}
+
+/// The least and most power the buildings draw over a craft, scaled the same way as
+/// their average power usage.  Only buildings with variable power usage have a range.
+fn production_power_range(
+    recipe: &Recipe,
+    building_count: FloatType,
+    clock_speed: FloatType,
+) -> Option<RecipePower> {
+    match recipe.building.as_manufacturer().power_consumption {
+        PowerConsumption::Variable { exponent, .. } => {
+            let scale = building_count.floor() * (clock_speed / 100.0).powf(exponent)
+                + (building_count.fract() * clock_speed / 100.0).powf(exponent);
+            Some(RecipePower {
+                min_mw: recipe.power.min_mw * scale,
+                max_mw: recipe.power.max_mw * scale,
+            })
+        }
+        PowerConsumption::Fixed { .. } => None,
+    }
+}
+
+/// Returns each feedback loop in the graph as the set of nodes that form it.
+pub fn find_cycles(graph: &SolvedGraph) -> Vec<Vec<NodeIndex>> {
+    tarjan_scc(graph)
+        .into_iter()
+        .filter(|scc| scc.len() > 1 || graph.contains_edge(scc[0], scc[0]))
+        .collect()
+}
+
+/// Whether any amount of the items flowing out of `from` ends up at `to`.
+pub fn is_reachable(graph: &SolvedGraph, from: NodeIndex, to: NodeIndex) -> bool {
+    has_path_connecting(graph, from, to, None)
+}
+
+/// Returns the shortest chain of nodes that carries items from `from` to `to`, including
+/// both ends, or `None` if `to` is not reachable from `from`.
+pub fn path_between(graph: &SolvedGraph, from: NodeIndex, to: NodeIndex) -> Option<Vec<NodeIndex>> {
+    astar(graph, from, |i| i == to, |_| 1, |_| 0).map(|(_, path)| path)
+}
+
+fn cleanup_by_product_nodes(graph: &mut SolvedGraph, epsilon: FloatType) {
     let by_product_nodes: Vec<NodeIndex> = graph
         .node_indices()
         .filter(|i| graph[*i].is_by_product())
@@ -184,10 +454,10 @@ fn cleanup_by_product_nodes(graph: &mut SolvedGraph) {
 
     by_product_nodes
         .iter()
-        .for_each(|i| cleanup_by_product(graph, *i));
+        .for_each(|i| cleanup_by_product(graph, *i, epsilon));
 }
 
-fn cleanup_by_product(graph: &mut SolvedGraph, node_idx: NodeIndex) {
+fn cleanup_by_product(graph: &mut SolvedGraph, node_idx: NodeIndex, epsilon: FloatType) {
     let mut parents: Vec<(NodeIndex, ItemPerMinute)> = graph
         .edges_directed(node_idx, Outgoing)
         .map(|e| (e.target(), e.weight().clone()))
@@ -200,17 +470,32 @@ fn cleanup_by_product(graph: &mut SolvedGraph, node_idx: NodeIndex) {
     parents.sort_unstable_by(|a, b| a.1.cmp(&b.1));
     children.sort_unstable_by(|a, b| a.1.cmp(&b.1).reverse());
 
-    let mut current_child = children.pop().unwrap();
-    for parent in parents {
+    let mut current_child = match children.pop() {
+        Some(child) => child,
+        None => return,
+    };
+
+    let mut children_exhausted = false;
+    'parents: for parent in parents {
         let mut remaining_output = parent.1;
         loop {
-            if remaining_output.is_zero() {
+            if is_zero_within(remaining_output.amount, epsilon) {
                 break;
             }
 
-            if current_child.1.is_zero() {
+            if is_zero_within(current_child.1.amount, epsilon) {
                 delete_edge_between(graph, current_child.0, node_idx);
-                current_child = children.pop().unwrap();
+                match children.pop() {
+                    Some(child) => current_child = child,
+                    None => {
+                        // rounding left the parents wanting more than the children supply, so
+                        // the residual stays on the by-product node
+                        let edge_index = graph.find_edge(node_idx, parent.0).unwrap();
+                        graph[edge_index] = remaining_output;
+                        children_exhausted = true;
+                        break 'parents;
+                    }
+                }
             }
 
             if remaining_output > current_child.1 {
@@ -227,8 +512,21 @@ fn cleanup_by_product(graph: &mut SolvedGraph, node_idx: NodeIndex) {
         delete_edge_between(graph, node_idx, parent.0);
     }
 
-    let remaining_output = clamp_to_zero(
+    if children_exhausted {
+        let residual: FloatType = graph
+            .edges_directed(node_idx, Outgoing)
+            .map(|e| e.weight().amount)
+            .sum();
+        match &mut graph[node_idx] {
+            SolvedNodeWeight::ByProduct(by_product) => by_product.amount = residual,
+            _ => panic!("Node is not a ByProduct"),
+        };
+        return;
+    }
+
+    let remaining_output = clamp_to_zero_within(
         current_child.1.amount + children.iter().map(|c| c.1.amount).sum::<FloatType>(),
+        epsilon,
     );
     if remaining_output > 0.0 {
         match &mut graph[node_idx] {
@@ -236,7 +534,7 @@ fn cleanup_by_product(graph: &mut SolvedGraph, node_idx: NodeIndex) {
             _ => panic!("Node is not a ByProduct"),
         };
 
-        if !current_child.1.is_zero() {
+        if !is_zero_within(current_child.1.amount, epsilon) {
             let edge_index = graph.find_edge(current_child.0, node_idx).unwrap();
             graph[edge_index] = current_child.1
         }
@@ -245,6 +543,72 @@ fn cleanup_by_product(graph: &mut SolvedGraph, node_idx: NodeIndex) {
     }
 }
 
+/// Turns the excess of every by-product into an output, merging it into the existing
+/// output node when the item is already an output.  By-products left feeding other nodes
+/// due to rounding aren't excess, so they are left alone.
+pub fn promote_by_products_to_outputs(graph: &mut SolvedGraph) {
+    let by_product_nodes: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|i| graph[*i].is_by_product())
+        .filter(|i| graph.edges_directed(*i, Outgoing).next().is_none())
+        .collect();
+
+    for i in by_product_nodes {
+        let by_product = match &graph[i] {
+            SolvedNodeWeight::ByProduct(by_product) => by_product.clone(),
+            _ => unreachable!(),
+        };
+
+        let output_idx = graph.node_indices().find(
+            |j| matches!(&graph[*j], SolvedNodeWeight::Output(output) if output.item == by_product.item),
+        );
+        match output_idx {
+            Some(output_idx) => {
+                let children: Vec<(NodeIndex, ItemPerMinute)> = graph
+                    .edges_directed(i, Incoming)
+                    .map(|e| (e.source(), e.weight().clone()))
+                    .collect();
+                for (child_idx, amount) in children {
+                    match graph.find_edge(child_idx, output_idx) {
+                        Some(e) => graph[e] += amount.amount,
+                        None => {
+                            graph.add_edge(child_idx, output_idx, amount);
+                        }
+                    }
+                }
+
+                if let SolvedNodeWeight::Output(output) = &mut graph[output_idx] {
+                    *output += by_product.amount;
+                }
+                graph.remove_node(i);
+            }
+            None => graph[i] = SolvedNodeWeight::Output(by_product),
+        }
+    }
+}
+
+/// Merges edges carrying the same item between the same pair of nodes into a single edge
+/// with their summed amount, as moving by-products around can leave more than one.
+pub fn coalesce_parallel_edges(graph: &mut SolvedGraph) {
+    let mut merged: HashMap<(NodeIndex, NodeIndex, Rc<Item>), EdgeIndex> = HashMap::new();
+    let edges: Vec<EdgeIndex> = graph.edge_indices().collect();
+
+    for e in edges {
+        let (source, target) = graph.edge_endpoints(e).unwrap();
+        let key = (source, target, Rc::clone(&graph[e].item));
+        match merged.get(&key) {
+            Some(kept) => {
+                let amount = graph[e].amount;
+                graph[*kept].amount += amount;
+                graph.remove_edge(e);
+            }
+            None => {
+                merged.insert(key, e);
+            }
+        }
+    }
+}
+
 fn delete_edge_between(graph: &mut SolvedGraph, a: NodeIndex, b: NodeIndex) -> bool {
     graph
         .find_edge(a, b)
@@ -254,3 +618,414 @@ fn delete_edge_between(graph: &mut SolvedGraph, a: NodeIndex, b: NodeIndex) -> b
         })
         .unwrap_or(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::test::{get_game_db_with_base_recipes_plus, get_test_game_db_with_recipes},
+        plan::{solve, solved_graph_to_dot, PlanConfig},
+    };
+
+    #[test]
+    fn find_cycles_recycled_rubber_plastic_loop() {
+        let game_db = get_game_db_with_base_recipes_plus(&[
+            "Recipe_Alternate_HeavyOilResidue_C",
+            "Recipe_Alternate_DilutedFuel_C",
+            "Recipe_Alternate_Plastic_1_C",
+            "Recipe_Alternate_RecycledRubber_C",
+        ]);
+
+        let plastic = game_db.find_item("Desc_Plastic_C").unwrap();
+        let rubber = game_db.find_item("Desc_Rubber_C").unwrap();
+        let config = PlanConfig::new(
+            vec![
+                ItemPerMinute::new(rubber, 300.0),
+                ItemPerMinute::new(plastic, 300.0),
+            ],
+            game_db,
+        );
+        let graph = solve(&config).unwrap();
+
+        let cycles = find_cycles(&graph);
+        assert_eq!(cycles.len(), 1);
+
+        let mut recipes: Vec<&str> = cycles[0]
+            .iter()
+            .map(|i| match &graph[*i] {
+                SolvedNodeWeight::Production { recipe, .. } => recipe.key.as_str(),
+                n => panic!("Unexpected node in cycle: {}", n),
+            })
+            .collect();
+        recipes.sort();
+        assert_eq!(
+            recipes,
+            vec![
+                "Recipe_Alternate_Plastic_1_C",
+                "Recipe_Alternate_RecycledRubber_C"
+            ]
+        );
+    }
+
+    #[test]
+    fn nodes_are_topologically_ordered() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let nodes: Vec<String> = graph
+            .node_weights()
+            .map(|n| match n {
+                SolvedNodeWeight::Input(input) => input.item.key.clone(),
+                SolvedNodeWeight::Output(output) => output.item.key.clone(),
+                SolvedNodeWeight::Production { recipe, .. } => recipe.key.clone(),
+                n => panic!("Unexpected node: {}", n),
+            })
+            .collect();
+        assert_eq!(
+            nodes,
+            vec![
+                "Desc_OreIron_C",
+                "Recipe_IngotIron_C",
+                "Recipe_IronPlate_C",
+                "Desc_IronPlate_C"
+            ]
+        );
+    }
+
+    #[test]
+    fn cleanup_by_product_with_exhausted_children() {
+        let game_db = get_test_game_db_with_recipes(&[]);
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+
+        // the by-product node passes on more than it receives, as can happen when small
+        // amounts are rounded to zero
+        let mut graph = SolvedGraph::new();
+        let child = graph.add_node(SolvedNodeWeight::new_input(iron_ingot.clone(), 10.0));
+        let by_product = graph.add_node(SolvedNodeWeight::new_by_product(iron_ingot.clone(), 10.0));
+        let parent_a = graph.add_node(SolvedNodeWeight::new_output(iron_ingot.clone(), 5.0));
+        let parent_b = graph.add_node(SolvedNodeWeight::new_output(iron_ingot.clone(), 7.0));
+        graph.add_edge(
+            child,
+            by_product,
+            ItemPerMinute::new(iron_ingot.clone(), 10.0),
+        );
+        graph.add_edge(
+            by_product,
+            parent_a,
+            ItemPerMinute::new(iron_ingot.clone(), 5.0),
+        );
+        graph.add_edge(
+            by_product,
+            parent_b,
+            ItemPerMinute::new(iron_ingot.clone(), 7.0),
+        );
+
+        cleanup_by_product_nodes(&mut graph, EPSILON);
+
+        let edge_amount = |a, b| graph[graph.find_edge(a, b).unwrap()].amount;
+        assert!(graph.find_edge(child, by_product).is_none());
+        assert_eq!(edge_amount(child, parent_a), 5.0);
+        assert_eq!(edge_amount(child, parent_b), 5.0);
+        assert_eq!(edge_amount(by_product, parent_b), 2.0);
+        assert!(matches!(
+            &graph[by_product],
+            SolvedNodeWeight::ByProduct(by_product) if by_product.amount == 2.0
+        ));
+    }
+
+    #[test]
+    fn coalesce_parallel_edges_by_item() {
+        let game_db = get_test_game_db_with_recipes(&[]);
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let copper_ingot = game_db.find_item("Desc_CopperIngot_C").unwrap();
+
+        let mut graph = SolvedGraph::new();
+        let a = graph.add_node(SolvedNodeWeight::new_input(iron_ingot.clone(), 15.0));
+        let b = graph.add_node(SolvedNodeWeight::new_output(iron_ingot.clone(), 15.0));
+        graph.add_edge(a, b, ItemPerMinute::new(iron_ingot.clone(), 10.0));
+        graph.add_edge(a, b, ItemPerMinute::new(copper_ingot.clone(), 3.0));
+        graph.add_edge(a, b, ItemPerMinute::new(iron_ingot.clone(), 5.0));
+
+        coalesce_parallel_edges(&mut graph);
+
+        let mut edges: Vec<(&str, FloatType)> = graph
+            .edge_weights()
+            .map(|e| (e.item.key.as_str(), e.amount))
+            .collect();
+        edges.sort_by(|x, y| x.0.cmp(y.0));
+        assert_eq!(
+            edges,
+            vec![("Desc_CopperIngot_C", 3.0), ("Desc_IronIngot_C", 15.0)]
+        );
+    }
+
+    #[test]
+    fn cleanup_by_product_with_looser_epsilon() {
+        let game_db = get_test_game_db_with_recipes(&[]);
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+
+        // accumulated error leaves the children supplying slightly more than the parent uses
+        let build_graph = || {
+            let mut graph = SolvedGraph::new();
+            let child = graph.add_node(SolvedNodeWeight::new_input(iron_ingot.clone(), 10.00001));
+            let by_product =
+                graph.add_node(SolvedNodeWeight::new_by_product(iron_ingot.clone(), 10.0));
+            let parent = graph.add_node(SolvedNodeWeight::new_output(iron_ingot.clone(), 10.0));
+            graph.add_edge(
+                child,
+                by_product,
+                ItemPerMinute::new(iron_ingot.clone(), 10.00001),
+            );
+            graph.add_edge(
+                by_product,
+                parent,
+                ItemPerMinute::new(iron_ingot.clone(), 10.0),
+            );
+            graph
+        };
+
+        let mut graph = build_graph();
+        cleanup_by_product_nodes(&mut graph, EPSILON);
+        assert_eq!(graph.node_count(), 3);
+
+        let mut graph = build_graph();
+        cleanup_by_product_nodes(&mut graph, 0.0001);
+        assert_eq!(graph.node_count(), 2);
+        assert!(graph.node_weights().all(|n| !n.is_by_product()));
+    }
+
+    #[test]
+    fn ore_reaches_iron_rods_and_plates() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronPlate_C",
+            "Recipe_IronRod_C",
+        ]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let iron_rod = game_db.find_item("Desc_IronRod_C").unwrap();
+        let config = PlanConfig::new(
+            vec![
+                ItemPerMinute::new(iron_rod, 30.0),
+                ItemPerMinute::new(iron_plate, 60.0),
+            ],
+            game_db,
+        );
+        let graph = solve(&config).unwrap();
+
+        let find_node = |key: &str| {
+            graph
+                .node_indices()
+                .find(|i| match &graph[*i] {
+                    SolvedNodeWeight::Input(input) => input.item.key == key,
+                    SolvedNodeWeight::Output(output) => output.item.key == key,
+                    SolvedNodeWeight::Production { recipe, .. } => recipe.key == key,
+                    _ => false,
+                })
+                .unwrap()
+        };
+        let ore = find_node("Desc_OreIron_C");
+        let plate = find_node("Desc_IronPlate_C");
+        let rod = find_node("Desc_IronRod_C");
+
+        assert!(is_reachable(&graph, ore, plate));
+        assert!(is_reachable(&graph, ore, rod));
+        assert!(!is_reachable(&graph, plate, ore));
+        assert!(!is_reachable(&graph, plate, rod));
+
+        assert_eq!(
+            path_between(&graph, ore, plate),
+            Some(vec![
+                ore,
+                find_node("Recipe_IngotIron_C"),
+                find_node("Recipe_IronPlate_C"),
+                plate
+            ])
+        );
+        assert_eq!(path_between(&graph, rod, ore), None);
+    }
+
+    #[test]
+    fn exact_rates_for_six_second_recipe() {
+        let game_db =
+            get_test_game_db_with_recipes(&["Recipe_IngotCopper_C", "Recipe_CopperSheet_C"]);
+
+        let copper_sheet = game_db.find_item("Desc_CopperSheet_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(copper_sheet, 35.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let mut rates: Vec<(String, FloatType)> = graph
+            .edge_weights()
+            .map(|e| (e.item.key.clone(), e.amount))
+            .collect();
+        rates.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            rates,
+            vec![
+                ("Desc_CopperIngot_C".to_string(), 70.0),
+                ("Desc_CopperSheet_C".to_string(), 35.0),
+                ("Desc_OreCopper_C".to_string(), 70.0),
+            ]
+        );
+
+        let recipe = config.game_db.find_recipe("Recipe_CopperSheet_C").unwrap();
+        let copper_ingot = config.game_db.find_item("Desc_CopperIngot_C").unwrap();
+        assert_eq!(recipe.input_rate(&copper_ingot, 3.5), Some(70.0));
+    }
+
+    #[test]
+    fn power_range_for_variable_power_buildings() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_Alternate_InstantPlutoniumCell_C",
+        ]);
+
+        let plutonium_cell = game_db.find_item("Desc_PlutoniumCell_C").unwrap();
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let inputs = HashMap::from([
+            (
+                game_db.find_item("Desc_NonFissibleUranium_C").unwrap(),
+                1000.0,
+            ),
+            (game_db.find_item("Desc_AluminumCasing_C").unwrap(), 1000.0),
+        ]);
+        let config = PlanConfig::with_inputs(
+            inputs,
+            vec![
+                ItemPerMinute::new(plutonium_cell, 20.0),
+                ItemPerMinute::new(iron_ingot, 30.0),
+            ],
+            game_db,
+        );
+        let graph = solve(&config).unwrap();
+
+        let power_ranges: HashMap<&str, Option<RecipePower>> = graph
+            .node_weights()
+            .filter_map(|n| match n {
+                SolvedNodeWeight::Production {
+                    recipe,
+                    power_range,
+                    ..
+                } => Some((recipe.key.as_str(), *power_range)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            power_ranges,
+            HashMap::from([
+                (
+                    "Recipe_Alternate_InstantPlutoniumCell_C",
+                    Some(RecipePower {
+                        min_mw: 500.0,
+                        max_mw: 1500.0
+                    })
+                ),
+                ("Recipe_IngotIron_C", None)
+            ])
+        );
+    }
+
+    #[test]
+    fn round_amounts_to_digits() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut config =
+            PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 200.0 / 3.0)], game_db);
+        let graph = solve(&config).unwrap();
+        assert!(graph.edge_weights().all(|e| e.amount != 66.67));
+
+        config.round_digits = Some(2);
+        let graph = solve(&config).unwrap();
+        assert!(graph.edge_weights().all(|e| e.amount == 66.67));
+        assert!(graph.node_weights().all(|n| match n {
+            SolvedNodeWeight::Input(item) | SolvedNodeWeight::Output(item) => item.amount == 66.67,
+            _ => true,
+        }));
+    }
+
+    #[test]
+    fn crafts_per_min_with_clock_speed() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 45.0)], game_db);
+        config.round_to_whole_buildings = true;
+        let graph = solve(&config).unwrap();
+
+        // 2 smelters at 75% each crafting an ingot every 2 seconds
+        let crafts_per_min = graph.node_weights().find_map(|n| n.crafts_per_min());
+        assert_eq!(crafts_per_min.map(|c| round(c, 3)), Some(45.0));
+    }
+
+    #[test]
+    fn node_ids_are_stable_across_solves() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        let node_ids = |graph: &SolvedGraph| {
+            let mut ids: Vec<String> = graph.node_weights().map(|n| n.id()).collect();
+            ids.sort();
+            ids
+        };
+
+        let first = node_ids(&solve(&config).unwrap());
+        config.outputs[0].amount = 100.0;
+        let second = node_ids(&solve(&config).unwrap());
+
+        assert_eq!(
+            first,
+            vec![
+                "input:Desc_OreIron_C",
+                "output:Desc_IronPlate_C",
+                "prod:Recipe_IngotIron_C",
+                "prod:Recipe_IronPlate_C"
+            ]
+        );
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn find_cycles_acyclic() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        assert!(find_cycles(&graph).is_empty());
+    }
+
+    #[test]
+    fn labels_in_plan_time_unit() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        config.time_unit = TimeUnit::Second;
+        let graph = solve(&config).unwrap();
+
+        let mut labels: Vec<String> = graph
+            .node_weights()
+            .map(|n| n.label(config.time_unit))
+            .collect();
+        labels.sort();
+        assert_eq!(
+            labels,
+            vec![
+                "Iron Ingot\n2x Smelter\n1 crafts / s, 2 s each",
+                "Iron Ore\n1 / s",
+                "Iron Plate\n0.667 / s",
+                "Iron Plate\n2x Constructor\n0.333 crafts / s, 6 s each",
+            ]
+        );
+
+        let dot = solved_graph_to_dot(&graph, config.time_unit);
+        assert!(dot.contains("Iron Ore\\n1 / s"));
+        assert!(!dot.contains("/ min"));
+    }
+}
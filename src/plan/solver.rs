@@ -1,57 +1,590 @@
-use good_lp::{minilp, variable, variables, Expression, SolverModel, Variable};
+use anyhow::bail;
+use good_lp::{
+    minilp, variable, variables, Expression, ProblemVariables, ResolutionError, Solution,
+    SolverModel, Variable,
+};
 use petgraph::{
     stable_graph::{EdgeIndex, NodeIndex},
     visit::EdgeRef,
     Direction::{Incoming, Outgoing},
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, rc::Rc, time::Instant};
 
-use crate::{game::Building, utils::FloatType};
+use crate::{
+    game::Building,
+    utils::{is_zero, is_zero_within, FloatType, EPSILON},
+};
 
 use super::{
-    full_plan_graph::{build_full_plan, PlanNodeWeight},
-    solved_graph::{copy_solution, SolvedGraph},
-    PlanConfig,
+    full_plan_graph::{FullPlanGraph, PlanNodeWeight},
+    solved_graph::{
+        copy_solution, promote_by_products_to_outputs, round_amounts, round_to_whole_buildings,
+        SolvedGraph, SolvedNodeWeight,
+    },
+    FullPlanCache, NodeWeight, ObjectiveMode, PlanConfig, PlanError,
 };
 
 const RESOURCE_WEIGHT: FloatType = 10_000.0;
+const SINK_POINTS_WEIGHT: FloatType = 10_000.0;
+const TARGET_OUTPUT_WEIGHT: FloatType = 10_000.0;
+const MAX_BRANCH_AND_BOUND_ITERATIONS: usize = 1_000;
+const TIE_BREAK_KEY_WEIGHT: FloatType = 0.001;
+/// Scales the tie-break cost of recipes producing more than one of the plan's outputs, so
+/// a joint recipe is preferred over separate chains for each output
+const TIE_BREAK_JOINT_OUTPUT_WEIGHT: FloatType = 0.01;
+/// Upper bound on a production node's building count used to link it to its active
+/// indicator when no tighter bound is known
+const SIMPLICITY_BIG_M: FloatType = 10_000.0;
+/// Upper bound on an input's items per minute used to link it to its active indicator
+/// when its limit is higher
+const INPUT_BIG_M: FloatType = 1_000_000.0;
+/// How far, relative to its size, the objective can drift from its optimal value when it
+/// is held for a later pass, so large objectives aren't held tighter than the solver can
+/// reach
+const OBJECTIVE_RELATIVE_TOLERANCE: FloatType = 1e-9;
+
+/// Lower and upper bounds on the building count of production nodes
+type BuildingBounds = HashMap<NodeIndex, (FloatType, FloatType)>;
+
+/// Production nodes whose active indicator has been fixed on or off
+type ActiveNodes = HashMap<NodeIndex, bool>;
+
+/// What a single pass over the plan's linear program minimizes
+enum LpPass<'a> {
+    /// The plan's objective
+    Objective,
+    /// A deterministic tie-break between the solutions reaching the optimal objective
+    TieBreak(FloatType),
+    /// The number of active production nodes, or resource inputs for `min_inputs`, among
+    /// the solutions reaching the optimal objective when one is given
+    Simplicity(Option<FloatType>, &'a ActiveNodes),
+}
+
+struct LpSolution {
+    graph: SolvedGraph,
+    objective: FloatType,
+    building_counts: Vec<(NodeIndex, FloatType)>,
+    active_indicators: Vec<(NodeIndex, FloatType)>,
+}
 
+/// Solves a single plan with a cache of its own.  Solving several plans should share a
+/// `FullPlanCache` between them through `solve_with_cache`.
 pub fn solve(config: &PlanConfig) -> Result<SolvedGraph, anyhow::Error> {
-    let full_graph = build_full_plan(config)?;
+    solve_with_cache(config, &mut FullPlanCache::default())
+}
+
+pub fn solve_with_cache(
+    config: &PlanConfig,
+    cache: &mut FullPlanCache,
+) -> Result<SolvedGraph, anyhow::Error> {
+    solve_with_objective(config, cache).map(|solved_plan| solved_plan.graph)
+}
+
+/// A solved plan along with the value its objective was solved to
+#[derive(Debug, Clone)]
+pub struct SolvedPlan {
+    pub graph: SolvedGraph,
+    pub objective_value: FloatType,
+    /// Names of the resources used all the way up to their limit, scaling the plan up needs
+    /// more of these
+    pub saturated_resources: Vec<String>,
+}
+
+pub fn solve_with_objective(
+    config: &PlanConfig,
+    cache: &mut FullPlanCache,
+) -> Result<SolvedPlan, anyhow::Error> {
+    let deadline = config.solve_timeout.map(|timeout| Instant::now() + timeout);
+    match solve_plan(config, cache, deadline) {
+        // only a plan the limits make infeasible can be diagnosed by relaxing them, every
+        // other error is returned as is
+        Err(e) if is_unsolvable(&e) => {
+            let binding_resources = find_binding_resources(config, cache, deadline);
+            if binding_resources.is_empty() {
+                Err(e)
+            } else {
+                Err(PlanError::InsufficientResources(binding_resources).into())
+            }
+        }
+        result => result,
+    }
+}
+
+/// Re-solves the plan with each resource limit relaxed one at a time and returns the
+/// names of the resources whose relaxation makes the plan solvable.
+fn find_binding_resources(
+    config: &PlanConfig,
+    cache: &mut FullPlanCache,
+    deadline: Option<Instant>,
+) -> Vec<String> {
+    let mut resources: Vec<String> = config
+        .inputs
+        .keys()
+        .filter(|item| item.resource)
+        .filter(|item| {
+            let mut relaxed_config = config.clone();
+            relaxed_config
+                .inputs
+                .insert(Rc::clone(item), FloatType::INFINITY);
+            solve_plan(&relaxed_config, cache, deadline).is_ok()
+        })
+        .map(|item| item.name.clone())
+        .collect();
+
+    resources.sort();
+    resources
+}
+
+fn solve_plan(
+    config: &PlanConfig,
+    cache: &mut FullPlanCache,
+    deadline: Option<Instant>,
+) -> Result<SolvedPlan, anyhow::Error> {
+    check_deadline(config, deadline)?;
+    let full_graph = cache.get_or_build(config, deadline)?;
+    check_deadline(config, deadline)?;
+
+    let solution = if config.integer_buildings {
+        solve_integer(config, &full_graph, deadline)?
+    } else {
+        solve_lp(config, &full_graph, &BuildingBounds::new(), deadline)?
+    };
+    let mut solved_graph = solution.graph;
+    let saturated_resources = find_saturated_resources(config, &solved_graph);
+
+    if config.round_to_whole_buildings {
+        round_to_whole_buildings(&mut solved_graph);
+    }
+
+    if config.promote_byproducts_to_outputs {
+        promote_by_products_to_outputs(&mut solved_graph);
+    }
+
+    if let Some(round_digits) = config.round_digits {
+        round_amounts(&mut solved_graph, round_digits);
+    }
+
+    Ok(SolvedPlan {
+        graph: solved_graph,
+        objective_value: solution.objective,
+        saturated_resources,
+    })
+}
+
+fn find_saturated_resources(config: &PlanConfig, graph: &SolvedGraph) -> Vec<String> {
+    let mut resources: Vec<String> = graph
+        .node_weights()
+        .filter_map(|n| match n {
+            SolvedNodeWeight::Input(input) => Some(input),
+            _ => None,
+        })
+        .filter(|input| {
+            input.item.resource
+                && is_zero_within(
+                    config.find_input(&input.item) - input.amount,
+                    config.epsilon,
+                )
+        })
+        .map(|input| input.item.name.clone())
+        .collect();
+
+    resources.sort();
+    resources
+}
+
+/// Branch and bound over the production building counts, returning the lowest cost plan
+/// where every production node has a whole number of buildings.
+fn solve_integer(
+    config: &PlanConfig,
+    full_graph: &FullPlanGraph,
+    deadline: Option<Instant>,
+) -> Result<LpSolution, anyhow::Error> {
+    let relaxed_solution = solve_lp(config, full_graph, &BuildingBounds::new(), deadline)?;
+
+    let mut best_solution: Option<LpSolution> = None;
+    let mut pending_bounds = vec![BuildingBounds::new()];
+    let mut iterations = 0;
+
+    while let Some(bounds) = pending_bounds.pop() {
+        if iterations == MAX_BRANCH_AND_BOUND_ITERATIONS {
+            eprintln!(
+                "Warning: Unable to find whole building counts within {} iterations, using the best solution found so far",
+                MAX_BRANCH_AND_BOUND_ITERATIONS
+            );
+            return Ok(best_solution.unwrap_or(relaxed_solution));
+        }
+        iterations += 1;
+        check_deadline(config, deadline)?;
+
+        let solution = match solve_lp(config, full_graph, &bounds, deadline) {
+            Ok(solution) => solution,
+            Err(..) => continue,
+        };
+
+        if let Some(best) = &best_solution {
+            if solution.objective >= best.objective - EPSILON {
+                continue;
+            }
+        }
+
+        let fractional = solution
+            .building_counts
+            .iter()
+            .find(|(_, count)| !is_zero(count - count.round()));
+
+        match fractional {
+            Some((idx, count)) => {
+                let (lower, upper) = bounds
+                    .get(idx)
+                    .copied()
+                    .unwrap_or((0.0, FloatType::INFINITY));
+
+                let mut round_down = bounds.clone();
+                round_down.insert(*idx, (lower, count.floor()));
+                let mut round_up = bounds;
+                round_up.insert(*idx, (count.ceil(), upper));
+
+                pending_bounds.push(round_down);
+                pending_bounds.push(round_up);
+            }
+            None => best_solution = Some(solution),
+        }
+    }
+
+    match best_solution {
+        Some(solution) => Ok(solution),
+        None => bail!(PlanError::UnsolvablePlan),
+    }
+}
+
+/// Fails with `RequestTooLarge` once the plan's solve timeout has passed.  minilp has no
+/// way to interrupt a solve, so a single linear program always runs to completion and
+/// the timeout is only checked in between the steps of building and solving a plan.
+/// A plan can overrun its timeout by as long as its slowest linear program takes.
+pub(super) fn check_deadline(
+    config: &PlanConfig,
+    deadline: Option<Instant>,
+) -> Result<(), PlanError> {
+    match (deadline, config.solve_timeout) {
+        (Some(deadline), Some(timeout)) if Instant::now() >= deadline => {
+            Err(PlanError::RequestTooLarge(format!(
+                "solving took longer than the limit of {} seconds",
+                timeout.as_secs_f64()
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn is_unsolvable(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<PlanError>(),
+        Some(PlanError::UnsolvablePlan)
+    )
+}
+
+fn is_request_too_large(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<PlanError>(),
+        Some(PlanError::RequestTooLarge(..))
+    )
+}
+
+/// Solves the plan's linear program.  Degenerate plans can have many equally optimal
+/// solutions and the one minilp lands on depends on how the problem is laid out, so the
+/// plan is solved a second time with the objective held at its optimal value to break
+/// ties deterministically.  The tie-break minimizes a single weighted sum of the building
+/// counts rather than ranking them strictly, where:
+///
+/// - buildings of recipes producing more than one of the plan's outputs cost a fraction
+///   (`TIE_BREAK_JOINT_OUTPUT_WEIGHT`) of the others'
+/// - buildings of recipes whose keys sort later cost up to `TIE_BREAK_KEY_WEIGHT` more
+///
+/// so a plan with slightly fewer buildings can still lose to one whose recipe keys sort
+/// first.  When the tie-break fails the first solution is kept, and the failure is
+/// reported if the plan is being debugged.
+///
+/// The `simplicity` objective replaces the tie-break with a search for the solution that
+/// uses the fewest distinct recipes.
+fn solve_lp(
+    config: &PlanConfig,
+    full_graph: &FullPlanGraph,
+    bounds: &BuildingBounds,
+    deadline: Option<Instant>,
+) -> Result<LpSolution, anyhow::Error> {
+    let solution = solve_lp_pass(config, full_graph, bounds, LpPass::Objective)?;
+    check_deadline(config, deadline)?;
+    let pass = match config.objective {
+        ObjectiveMode::Simplicity => solve_simplicity(
+            config,
+            full_graph,
+            bounds,
+            Some(solution.objective),
+            deadline,
+        ),
+        ObjectiveMode::MinInputs => solve_min_inputs(config, full_graph, bounds, deadline),
+        _ => solve_lp_pass(
+            config,
+            full_graph,
+            bounds,
+            LpPass::TieBreak(solution.objective),
+        ),
+    };
+
+    match pass {
+        Err(e) if is_request_too_large(&e) => Err(e),
+        Err(e) => {
+            if config.debug {
+                eprintln!(
+                    "Debug: Keeping the first solution, the tie-break failed: {}",
+                    e
+                );
+            }
+            Ok(solution)
+        }
+        pass => pass,
+    }
+}
+
+/// The most the objective can exceed `objective` when it is held at its optimal value
+fn held_objective(objective: FloatType) -> FloatType {
+    objective + objective.abs() * OBJECTIVE_RELATIVE_TOLERANCE + EPSILON
+}
+
+/// Finds the fewest resource inputs the plan can be built from, then solves the plan with
+/// every other resource input unavailable for the lowest cost using just those.
+fn solve_min_inputs(
+    config: &PlanConfig,
+    full_graph: &FullPlanGraph,
+    bounds: &BuildingBounds,
+    deadline: Option<Instant>,
+) -> Result<LpSolution, anyhow::Error> {
+    let fewest_inputs = solve_simplicity(config, full_graph, bounds, None, deadline)?;
+
+    let mut restricted_config = config.clone();
+    restricted_config.objective = ObjectiveMode::Minimize;
+    for (idx, active) in &fewest_inputs.active_indicators {
+        if let PlanNodeWeight::Input(item) = &full_graph[*idx] {
+            if is_zero(*active) {
+                restricted_config.inputs.insert(Rc::clone(item), 0.0);
+            }
+        }
+    }
+
+    solve_lp(&restricted_config, full_graph, bounds, deadline)
+}
+
+fn add_active_variable(
+    vars: &mut ProblemVariables,
+    active_nodes: &ActiveNodes,
+    idx: NodeIndex,
+) -> Variable {
+    match active_nodes.get(&idx) {
+        Some(true) => vars.add(variable().min(1.0).max(1.0)),
+        Some(false) => vars.add(variable().min(0.0).max(0.0)),
+        None => vars.add(variable().min(0.0).max(1.0)),
+    }
+}
+
+/// Branch and bound over binary indicators of whether each production node, or resource
+/// input for `min_inputs`, is active, returning the solution with the fewest active nodes
+/// that reaches the optimal objective when one is given.
+fn solve_simplicity(
+    config: &PlanConfig,
+    full_graph: &FullPlanGraph,
+    bounds: &BuildingBounds,
+    optimal_objective: Option<FloatType>,
+    deadline: Option<Instant>,
+) -> Result<LpSolution, anyhow::Error> {
+    let mut best: Option<(FloatType, LpSolution)> = None;
+    let mut pending_active_nodes = vec![ActiveNodes::new()];
+    let mut iterations = 0;
+
+    while let Some(active_nodes) = pending_active_nodes.pop() {
+        if iterations == MAX_BRANCH_AND_BOUND_ITERATIONS {
+            break;
+        }
+        iterations += 1;
+        check_deadline(config, deadline)?;
+
+        let pass = LpPass::Simplicity(optimal_objective, &active_nodes);
+        let solution = match solve_lp_pass(config, full_graph, bounds, pass) {
+            Ok(solution) => solution,
+            Err(..) => continue,
+        };
+
+        let active_count: FloatType = solution.active_indicators.iter().map(|(_, a)| a).sum();
+        if let Some((best_count, _)) = &best {
+            if (active_count - EPSILON).ceil() >= *best_count {
+                continue;
+            }
+        }
+
+        let fractional = solution
+            .active_indicators
+            .iter()
+            .find(|(_, active)| !is_zero(active - active.round()));
+
+        match fractional {
+            Some((idx, _)) => {
+                let mut inactive = active_nodes.clone();
+                inactive.insert(*idx, false);
+                let mut active = active_nodes;
+                active.insert(*idx, true);
+
+                pending_active_nodes.push(active);
+                pending_active_nodes.push(inactive);
+            }
+            None => best = Some((active_count.round(), solution)),
+        }
+    }
+
+    match best {
+        Some((_, solution)) => Ok(solution),
+        None => bail!(PlanError::UnsolvablePlan),
+    }
+}
+
+fn solve_lp_pass(
+    config: &PlanConfig,
+    full_graph: &FullPlanGraph,
+    bounds: &BuildingBounds,
+    pass: LpPass,
+) -> Result<LpSolution, anyhow::Error> {
+    // a pinned recipe that was pruned from the graph can't be given its building count
+    for (recipe_key, building_count) in &config.pinned_recipes {
+        let in_graph = full_graph.node_weights().any(
+            |n| matches!(n, PlanNodeWeight::Production(recipe, ..) if recipe.key == *recipe_key),
+        );
+        if !in_graph && *building_count > 0.0 {
+            bail!(PlanError::UnsolvablePlan);
+        }
+    }
 
     let mut node_variables: HashMap<NodeIndex, Variable> = HashMap::new();
     let mut edge_variables: HashMap<EdgeIndex, Variable> = HashMap::new();
     let mut by_product_variables: HashMap<NodeIndex, Variable> = HashMap::new();
+    let mut active_variables: HashMap<NodeIndex, Variable> = HashMap::new();
 
     let mut vars = variables!();
     let mut resource_expr: Expression = 0.into();
+    let mut waste_expr: Expression = 0.into();
+    let mut target_resource_expr: Expression = 0.into();
+    let mut target_output_expr: Expression = 0.into();
     let mut complexity_expr: Expression = 0.into();
+    let mut power_expr: Expression = 0.into();
+    let mut floor_area_expr: Expression = 0.into();
+    let mut volume_expr: Expression = 0.into();
+    let mut building_count_expr: Expression = 0.into();
+    let mut building_exprs: HashMap<&str, Expression> = HashMap::new();
+    let mut power_output_expr: Expression = 0.into();
+    let mut sink_points_expr: Expression = 0.into();
+    let mut tie_break_expr: Expression = 0.into();
+    let mut active_expr: Expression = 0.into();
+
+    let mut recipe_keys: Vec<&str> = full_graph
+        .node_weights()
+        .filter_map(|n| match n {
+            PlanNodeWeight::Production(recipe, ..) => Some(recipe.key.as_str()),
+            _ => None,
+        })
+        .collect();
+    recipe_keys.sort();
 
     for i in full_graph.node_indices() {
         match &full_graph[i] {
             PlanNodeWeight::Input(item) => {
                 let var = vars.add(variable().min(0.0));
                 if item.resource {
-                    let limit = config.game_db.get_resource_limit(item);
-                    resource_expr += var * 10_000.0 / limit;
+                    resource_expr += var * config.resource_score(item);
                 }
+                if config.target_resource.as_ref() == Some(item) {
+                    target_resource_expr += var;
+                }
+                node_variables.insert(i, var);
 
+                if let LpPass::Simplicity(_, active_nodes) = pass {
+                    if item.resource && config.objective == ObjectiveMode::MinInputs {
+                        let active_var = add_active_variable(&mut vars, active_nodes, i);
+                        active_expr += active_var;
+                        active_variables.insert(i, active_var);
+                    }
+                }
+            }
+            PlanNodeWeight::Output(item) => {
+                let var = vars.add(variable().min(0.0));
+                sink_points_expr +=
+                    var * item.sink_points as FloatType * config.find_output_weight(item);
+                if config.target_output.as_ref() == Some(item) {
+                    target_output_expr += var;
+                }
                 node_variables.insert(i, var);
             }
-            PlanNodeWeight::ByProduct(..) => {
+            PlanNodeWeight::ByProduct(item) => {
                 let var = vars.add(variable().min(0.0));
                 let excess_var = vars.add(variable().min(0.0));
+                sink_points_expr += excess_var * item.sink_points;
+                waste_expr += excess_var;
 
                 node_variables.insert(i, var);
                 by_product_variables.insert(i, excess_var);
             }
-            PlanNodeWeight::Production(_, complexity) => {
-                let var = vars.add(variable().min(0.0));
+            PlanNodeWeight::Production(recipe, complexity) => {
+                let (lower, upper) = bounds
+                    .get(&i)
+                    .copied()
+                    .unwrap_or((0.0, FloatType::INFINITY));
+                let var = vars.add(variable().min(lower).max(upper));
                 complexity_expr += var * *complexity;
+                power_expr += var * recipe.average_mw(100.0);
+                floor_area_expr += var * recipe.building.floor_area();
+                volume_expr += var * recipe.building.volume();
+                building_count_expr += var;
+                *building_exprs.entry(recipe.building.key()).or_default() += var;
+                let key_rank = recipe_keys.binary_search(&recipe.key.as_str()).unwrap();
+                let requested_outputs = recipe
+                    .outputs
+                    .iter()
+                    .filter(|o| config.outputs.iter().any(|output| output.item == o.item))
+                    .count();
+                let joint_output_weight = if requested_outputs > 1 {
+                    TIE_BREAK_JOINT_OUTPUT_WEIGHT
+                } else {
+                    1.0
+                };
+                tie_break_expr += var
+                    * joint_output_weight
+                    * (1.0
+                        + TIE_BREAK_KEY_WEIGHT * key_rank as FloatType
+                            / recipe_keys.len() as FloatType);
+                node_variables.insert(i, var);
+
+                if let LpPass::Simplicity(_, active_nodes) = pass {
+                    if config.objective == ObjectiveMode::Simplicity {
+                        let active_var = add_active_variable(&mut vars, active_nodes, i);
+                        active_expr += active_var;
+                        active_variables.insert(i, active_var);
+                    }
+                }
+            }
+            PlanNodeWeight::Producer(building) => {
+                let var = vars.add(variable().min(0.0));
+                floor_area_expr += var * building.floor_area();
+                volume_expr += var * building.volume();
+                building_count_expr += var;
+                *building_exprs.entry(building.key()).or_default() += var;
+                tie_break_expr += var;
                 node_variables.insert(i, var);
             }
-            _ => {
-                node_variables.insert(i, vars.add(variable().min(0.0)));
+            PlanNodeWeight::Generator(building, ..) => {
+                let var = vars.add(variable().min(0.0));
+                floor_area_expr += var * building.floor_area();
+                volume_expr += var * building.volume();
+                building_count_expr += var;
+                *building_exprs.entry(building.key()).or_default() += var;
+                tie_break_expr += var;
+                power_output_expr += var * building.as_power_generator().power_production_mw;
+                node_variables.insert(i, var);
             }
         }
     }
@@ -60,9 +593,71 @@ pub fn solve(config: &PlanConfig) -> Result<SolvedGraph, anyhow::Error> {
         edge_variables.insert(e, vars.add(variable().min(0.0)));
     }
 
-    let mut problem = vars
-        .minimise((RESOURCE_WEIGHT * resource_expr) + complexity_expr)
-        .using(minilp);
+    let weights = &config.objective_weights;
+    let minimize_expr = match config.objective {
+        ObjectiveMode::Minimize | ObjectiveMode::Simplicity | ObjectiveMode::MinInputs => {
+            (weights.resource * RESOURCE_WEIGHT * resource_expr)
+                + (weights.power * power_expr.clone())
+                + (weights.floor_area * floor_area_expr)
+                + (weights.volume * volume_expr)
+                + (config.waste_penalty * RESOURCE_WEIGHT * waste_expr)
+                + complexity_expr
+        }
+        ObjectiveMode::SinkPoints => complexity_expr - (SINK_POINTS_WEIGHT * sink_points_expr),
+        ObjectiveMode::MinimizeResource => target_resource_expr,
+        ObjectiveMode::MaximizeOutput => {
+            complexity_expr - (TARGET_OUTPUT_WEIGHT * target_output_expr)
+        }
+    };
+
+    let mut problem = match pass {
+        LpPass::Objective => vars.minimise(minimize_expr.clone()).using(minilp),
+        LpPass::TieBreak(objective) => vars
+            .minimise(tie_break_expr)
+            .using(minilp)
+            .with(minimize_expr.clone().leq(held_objective(objective))),
+        LpPass::Simplicity(objective, _) => {
+            let problem = vars.minimise(active_expr).using(minilp);
+            match objective {
+                Some(objective) => {
+                    problem.with(minimize_expr.clone().leq(held_objective(objective)))
+                }
+                None => problem,
+            }
+        }
+    };
+
+    // a production node can only have buildings, and an input can only be used, when its
+    // active indicator is on
+    for (i, active_var) in &active_variables {
+        let big_m = match (&full_graph[*i], bounds.get(i)) {
+            (PlanNodeWeight::Input(item), _) => config.find_input(item).min(INPUT_BIG_M),
+            (_, Some((_, upper))) if upper.is_finite() => *upper,
+            _ => config
+                .max_buildings
+                .map(|max| max as FloatType)
+                .unwrap_or(SIMPLICITY_BIG_M),
+        };
+        problem = problem.with((node_variables[i] - big_m * *active_var).leq(0.0));
+    }
+
+    for (building_key, building_expr) in building_exprs {
+        if let Some(limit) = config.building_limits.get(building_key) {
+            problem = problem.with(building_expr.leq(*limit));
+        }
+    }
+
+    if let Some(max_buildings) = config.max_buildings {
+        problem = problem.with(building_count_expr.leq(max_buildings));
+    }
+
+    if config.power_output_mw > 0.0 {
+        problem = problem.with(power_output_expr.eq(config.power_output_mw));
+    }
+
+    if let Some(max_power_mw) = config.max_power_mw {
+        problem = problem.with(power_expr.leq(max_power_mw));
+    }
 
     for i in full_graph.node_indices() {
         let var = *node_variables.get(&i).unwrap();
@@ -76,9 +671,18 @@ pub fn solve(config: &PlanConfig) -> Result<SolvedGraph, anyhow::Error> {
                 }
 
                 let desired_output = config.find_output(item);
-                problem = problem
-                    .with(Expression::from(var).eq(desired_output))
-                    .with(edge_sum.eq(var));
+                problem = match config.objective {
+                    ObjectiveMode::Minimize
+                    | ObjectiveMode::Simplicity
+                    | ObjectiveMode::MinimizeResource
+                    | ObjectiveMode::MinInputs => {
+                        problem.with(Expression::from(var).eq(desired_output))
+                    }
+                    ObjectiveMode::SinkPoints | ObjectiveMode::MaximizeOutput => {
+                        problem.with(Expression::from(var).geq(desired_output))
+                    }
+                };
+                problem = problem.with(edge_sum.eq(var));
             }
             PlanNodeWeight::Input(item) => {
                 let mut edge_sum: Expression = 0.into();
@@ -88,11 +692,12 @@ pub fn solve(config: &PlanConfig) -> Result<SolvedGraph, anyhow::Error> {
                 }
 
                 let limit = config.find_input(item);
-                problem = problem
-                    .with(Expression::from(var).leq(limit))
-                    .with(edge_sum.eq(var));
+                if limit.is_finite() {
+                    problem = problem.with(Expression::from(var).leq(limit));
+                }
+                problem = problem.with(edge_sum.eq(var));
             }
-            PlanNodeWeight::ByProduct(..) => {
+            PlanNodeWeight::ByProduct(item) => {
                 let excess_var = *by_product_variables.get(&i).unwrap();
 
                 let mut incoming_sum: Expression = 0.into();
@@ -110,8 +715,24 @@ pub fn solve(config: &PlanConfig) -> Result<SolvedGraph, anyhow::Error> {
                 problem = problem
                     .with(incoming_sum.eq(var))
                     .with(outgoing_sum.eq(var));
+
+                // when outputs are minimums, any surplus of a requested output should be
+                // delivered to that output instead of being left as a by-product
+                if config.no_waste
+                    || config.no_surplus_items.contains(item)
+                    || (matches!(
+                        config.objective,
+                        ObjectiveMode::SinkPoints | ObjectiveMode::MaximizeOutput
+                    ) && config.outputs.iter().any(|o| o.item == *item))
+                {
+                    problem = problem.with(Expression::from(excess_var).eq(0.0));
+                }
             }
             PlanNodeWeight::Production(recipe, ..) => {
+                if let Some(building_count) = config.pinned_recipes.get(&recipe.key) {
+                    problem = problem.with(Expression::from(var).eq(*building_count));
+                }
+
                 for edge in full_graph.edges_directed(i, Outgoing) {
                     let edge_var = edge_variables.get(&edge.id()).unwrap();
                     let recipe_output = recipe.find_output_by_item(edge.weight()).unwrap();
@@ -137,140 +758,1080 @@ pub fn solve(config: &PlanConfig) -> Result<SolvedGraph, anyhow::Error> {
                     problem = problem.with(edge_sum.eq(var * ip.output.amount));
                 }
             }
+            PlanNodeWeight::Generator(_, fuel) => {
+                for edge in full_graph.edges_directed(i, Incoming) {
+                    let edge_var = edge_variables.get(&edge.id()).unwrap();
+                    let fuel_input = fuel.find_input_by_item(edge.weight()).unwrap();
+
+                    problem = problem.with((var * fuel_input.amount).eq(edge_var));
+                }
+
+                for edge in full_graph.edges_directed(i, Outgoing) {
+                    let edge_var = edge_variables.get(&edge.id()).unwrap();
+                    let by_product = fuel.by_product.as_ref().unwrap();
+
+                    problem = problem.with((var * by_product.amount).eq(edge_var));
+                }
+            }
         }
     }
 
-    let solution = problem.solve()?;
-    Ok(copy_solution(
-        &full_graph,
-        solution,
-        node_variables,
-        edge_variables,
-    ))
+    let solution = match problem.solve() {
+        Ok(solution) => solution,
+        Err(ResolutionError::Infeasible) => bail!(PlanError::UnsolvablePlan),
+        Err(ResolutionError::Unbounded) => bail!(PlanError::UnboundedPlan),
+        Err(e) => return Err(e.into()),
+    };
+    // minilp can report an unbounded problem as solved with infinite variables
+    let objective = solution.eval(minimize_expr);
+    if !objective.is_finite() {
+        bail!(PlanError::UnboundedPlan);
+    }
+    let building_counts = full_graph
+        .node_indices()
+        .filter(|i| full_graph[*i].is_production())
+        .map(|i| (i, solution.value(node_variables[&i])))
+        .collect();
+    let active_indicators = active_variables
+        .iter()
+        .map(|(i, var)| (*i, solution.value(*var)))
+        .collect();
+
+    Ok(LpSolution {
+        graph: copy_solution(
+            full_graph,
+            solution,
+            node_variables,
+            edge_variables,
+            config.epsilon,
+            config.keep_unused,
+        ),
+        objective,
+        building_counts,
+        active_indicators,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use petgraph::visit::IntoEdgeReferences;
 
-    use super::*;
-    use crate::{
-        game::{
-            test::{get_game_db_with_base_recipes_plus, get_test_game_db_with_recipes},
-            ItemPerMinute,
-        },
-        plan::solved_graph::SolvedNodeWeight,
-        utils::{round, FloatType, EPSILON},
-    };
+    use super::*;
+    use crate::{
+        game::{
+            test::{get_game_db_with_base_recipes_plus, get_test_game_db_with_recipes},
+            GameDatabase, ItemPerMinute,
+        },
+        plan::solved_graph::SolvedNodeWeight,
+        utils::{round, FloatType, EPSILON},
+    };
+
+    macro_rules! graph_builder {
+        (
+            Graph($game_db:ident) {
+                nodes: [
+                    $($id:literal [$node_type:ident($($node_args:tt)*)]),*
+                ],
+                edges: [
+                    $($source:literal -> $target:literal [$item:literal, $amount:expr]),*
+                ]
+            }
+        ) => {
+            {
+                let mut temp_graph = SolvedGraph::new();
+                let mut node_mappings: HashMap<u32, NodeIndex> = HashMap::new();
+
+                $(
+                    {
+                        let weight = graph_builder!(@node($game_db) $node_type($($node_args)*));
+                        if !node_mappings.insert($id, temp_graph.add_node(weight)).is_none() {
+                            panic!("Node id {} was used multiple times", $id);
+                        }
+                    }
+                )*
+
+                $(
+                    {
+                        let src_id = *node_mappings.get(&$source).unwrap_or_else(||
+                            panic!("Source Node {} does not exist", $source));
+                        let target_id = *node_mappings.get(&$target).unwrap_or_else(||
+                            panic!("Target Node {} does not exist", $target));
+                        let item = $game_db.find_item($item).unwrap_or_else(||
+                            panic!("Item {} does not exist", $item));
+
+                        temp_graph.add_edge(src_id, target_id, ItemPerMinute::new(item, $amount));
+                    }
+                )*
+
+                temp_graph
+            }
+        };
+        (
+            @node($game_db:ident) Production($recipe: literal, $building_count:expr)
+        ) => {
+            SolvedNodeWeight::new_production(
+                $game_db.find_recipe($recipe).unwrap_or_else(||
+                    panic!("Recipe {} does not exist", $recipe)),
+                $building_count
+            )
+        };
+        (
+            @node($game_db:ident) Production($recipe: literal, $building_count:expr, $clock_speed:expr)
+        ) => {
+            SolvedNodeWeight::new_production_with_clock_speed(
+                $game_db.find_recipe($recipe).unwrap_or_else(||
+                    panic!("Recipe {} does not exist", $recipe)),
+                $building_count,
+                $clock_speed
+            )
+        };
+        (
+            @node($game_db:ident) Input($item:literal, $amount:expr)
+        ) => {
+            SolvedNodeWeight::new_input(
+                $game_db.find_item($item).unwrap_or_else(||
+                    panic!("Item {} does not exist", $item)),
+                $amount
+            )
+        };
+        (
+            @node($game_db:ident) Output($item:literal, $amount:expr)
+        ) => {
+            SolvedNodeWeight::new_output(
+                $game_db.find_item($item).unwrap_or_else(||
+                    panic!("Item {} does not exist", $item)),
+                $amount
+            )
+        };
+        (
+            @node($game_db:ident) ByProduct($item:literal, $amount:expr)
+        ) => {
+            SolvedNodeWeight::new_by_product(
+                $game_db.find_item($item).unwrap_or_else(||
+                    panic!("Item {} does not exist", $item)),
+                $amount
+            )
+        };
+        (
+            @node($game_db:ident) Producer($building: literal, $building_count:expr)
+        ) => {
+            SolvedNodeWeight::new_producer(
+                $game_db.find_building($building).unwrap_or_else(||
+                    panic!("Building {} does not exist", $building)),
+                $building_count
+            )
+        };
+        (
+            @node($game_db:ident) Generator($building: literal, $fuel:literal, $building_count:expr)
+        ) => {
+            {
+                let building = $game_db.find_building($building).unwrap_or_else(||
+                    panic!("Building {} does not exist", $building));
+                let fuel = building
+                    .as_power_generator()
+                    .fuels
+                    .iter()
+                    .find(|f| f.fuel.item.key == $fuel)
+                    .cloned()
+                    .unwrap_or_else(|| panic!("Fuel {} does not exist", $fuel));
+
+                SolvedNodeWeight::new_generator(building, fuel, $building_count)
+            }
+        };
+    }
+
+    #[test]
+    fn test_iron_ingot_base_recipes() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Output("Desc_IronIngot_C", 30.0)],
+                    1 [Production("Recipe_IngotIron_C", 1.0)],
+                    2 [Input("Desc_OreIron_C", 30.0)]
+                ],
+                edges: [
+                    2 -> 1 ["Desc_OreIron_C", 30.0],
+                    1 -> 0 ["Desc_IronIngot_C", 30.0]
+                ]
+            }
+        );
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 30.0)], game_db);
+
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
+    }
+
+    #[test]
+    fn test_iron_ingot_with_pure_ingot_recipe() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_Alternate_PureIronIngot_C",
+        ]);
+
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Output("Desc_IronIngot_C", 65.0)],
+                    1 [Production("Recipe_Alternate_PureIronIngot_C", 1.0)],
+                    2 [Input("Desc_OreIron_C", 35.0)],
+                    3 [Input("Desc_Water_C", 20.0)]
+                ],
+                edges: [
+                    3 -> 1 ["Desc_Water_C", 20.0],
+                    2 -> 1 ["Desc_OreIron_C", 35.0],
+                    1 -> 0 ["Desc_IronIngot_C", 65.0]
+                ]
+            }
+        );
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 65.0)], game_db);
+
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
+    }
+
+    #[test]
+    fn test_iron_ingot_with_power_weight() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_Alternate_PureIronIngot_C",
+        ]);
+
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Output("Desc_IronIngot_C", 65.0)],
+                    1 [Production("Recipe_IngotIron_C", 65.0 / 30.0)],
+                    2 [Input("Desc_OreIron_C", 65.0)]
+                ],
+                edges: [
+                    2 -> 1 ["Desc_OreIron_C", 65.0],
+                    1 -> 0 ["Desc_IronIngot_C", 65.0]
+                ]
+            }
+        );
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 65.0)], game_db);
+        config.objective_weights.power = 1_000_000.0;
+
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
+    }
+
+    #[test]
+    fn test_iron_ingot_with_water_weight() {
+        let mut game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_Alternate_PureIronIngot_C",
+        ]);
+        let water = game_db.find_item("Desc_Water_C").unwrap();
+        game_db.resource_limits.insert(Rc::clone(&water), 100.0);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut config =
+            PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 65.0)], game_db.clone());
+
+        // water is scarce enough that saving iron ore isn't worth it
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Output("Desc_IronIngot_C", 65.0)],
+                    1 [Production("Recipe_IngotIron_C", 65.0 / 30.0)],
+                    2 [Input("Desc_OreIron_C", 65.0)]
+                ],
+                edges: [
+                    2 -> 1 ["Desc_OreIron_C", 65.0],
+                    1 -> 0 ["Desc_IronIngot_C", 65.0]
+                ]
+            }
+        );
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
+
+        config.resource_weights.insert(water, 0.0);
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Output("Desc_IronIngot_C", 65.0)],
+                    1 [Production("Recipe_Alternate_PureIronIngot_C", 1.0)],
+                    2 [Input("Desc_OreIron_C", 35.0)],
+                    3 [Input("Desc_Water_C", 20.0)]
+                ],
+                edges: [
+                    2 -> 1 ["Desc_OreIron_C", 35.0],
+                    3 -> 1 ["Desc_Water_C", 20.0],
+                    1 -> 0 ["Desc_IronIngot_C", 65.0]
+                ]
+            }
+        );
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
+    }
+
+    #[test]
+    fn test_iron_ingot_with_max_buildings() {
+        let game_db =
+            get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_Alternate_IngotIron_C"]);
+
+        // Iron Alloy Ingot consumes the scarcer copper ore, but is the only way to
+        // produce 100 ingots with just 2 buildings
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Output("Desc_IronIngot_C", 100.0)],
+                    1 [Production("Recipe_Alternate_IngotIron_C", 2.0)],
+                    2 [Input("Desc_OreIron_C", 40.0)],
+                    3 [Input("Desc_OreCopper_C", 40.0)]
+                ],
+                edges: [
+                    2 -> 1 ["Desc_OreIron_C", 40.0],
+                    3 -> 1 ["Desc_OreCopper_C", 40.0],
+                    1 -> 0 ["Desc_IronIngot_C", 100.0]
+                ]
+            }
+        );
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 100.0)], game_db);
+        config.max_buildings = Some(2);
+
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
+    }
+
+    #[test]
+    fn test_iron_ingot_with_max_buildings_unsolvable() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 100.0)], game_db);
+        config.max_buildings = Some(2);
+
+        assert!(solve(&config).is_err());
+    }
+
+    #[test]
+    fn test_iron_plate_with_tiny_resource_limits() {
+        let game_db = get_game_db_with_base_recipes_plus(&["Recipe_Alternate_PureIronIngot_C"]);
+        let mut tiny_game_db = game_db.clone();
+        for limit in tiny_game_db.resource_limits.values_mut() {
+            *limit /= 1_000.0;
+        }
+
+        let recipes = |game_db: GameDatabase| -> Vec<String> {
+            let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+            let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 30.0)], game_db);
+            let mut recipes: Vec<String> = solve(&config)
+                .unwrap()
+                .node_weights()
+                .filter_map(|n| match n {
+                    SolvedNodeWeight::Production { recipe, .. } => Some(recipe.key.clone()),
+                    _ => None,
+                })
+                .collect();
+            recipes.sort();
+            recipes
+        };
+
+        // the resource scores are normalized against the limits, so scaling every limit
+        // down leaves the recipe selection unchanged
+        assert_eq!(recipes(tiny_game_db), recipes(game_db));
+    }
+
+    #[test]
+    fn test_iron_plate_with_building_limits() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronPlate_C",
+            "Recipe_Alternate_CoatedIronPlate_C",
+        ]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let plastic = game_db.find_item("Desc_Plastic_C").unwrap();
+        let mut config = PlanConfig::with_inputs(
+            HashMap::from([(plastic, 100.0)]),
+            vec![ItemPerMinute::new(iron_plate, 150.0)],
+            game_db,
+        );
+        config.objective = ObjectiveMode::MinimizeResource;
+        config.target_resource = Some(iron_ore);
+        let building_counts = |config: &PlanConfig| {
+            let result = solve(config).unwrap_or_else(|e| {
+                panic!("Failed to solve plan: {}", e);
+            });
+            let mut building_counts: Vec<(String, FloatType)> = result
+                .node_weights()
+                .filter_map(|n| match n {
+                    SolvedNodeWeight::Production {
+                        recipe,
+                        building_count,
+                        ..
+                    } => Some((recipe.key.clone(), round(*building_count, 3))),
+                    _ => None,
+                })
+                .collect();
+            building_counts.sort_by(|a, b| a.0.cmp(&b.0));
+            building_counts
+        };
+
+        assert_eq!(
+            building_counts(&config),
+            vec![
+                ("Recipe_Alternate_CoatedIronPlate_C".into(), 2.0),
+                ("Recipe_IngotIron_C".into(), 3.333)
+            ]
+        );
+
+        config
+            .building_limits
+            .insert("Desc_AssemblerMk1_C".into(), 1.0);
+        assert_eq!(
+            building_counts(&config),
+            vec![
+                ("Recipe_Alternate_CoatedIronPlate_C".into(), 1.0),
+                ("Recipe_IngotIron_C".into(), 5.417),
+                ("Recipe_IronPlate_C".into(), 3.75)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_iron_ingot_insufficient_resources() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let config = PlanConfig::with_inputs(
+            HashMap::from([(iron_ore, 30.0)]),
+            vec![ItemPerMinute::new(iron_ingot, 50.0)],
+            game_db,
+        );
+
+        let error = solve(&config).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PlanError>(),
+            Some(&PlanError::InsufficientResources(vec!["Iron Ore".into()]))
+        );
+    }
+
+    #[test]
+    fn test_no_recipe_for_output() {
+        let game_db =
+            get_game_db_with_base_recipes_plus(&[]).filter(|r| r.key != "Recipe_IronPlate_C");
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 30.0)], game_db);
+
+        let error = solve(&config).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PlanError>(),
+            Some(&PlanError::NoRecipeForOutput("Iron Plate".into()))
+        );
+    }
+
+    #[test]
+    fn test_sink_points_unbounded() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let mut config = PlanConfig::with_inputs(
+            HashMap::from([(iron_ore, FloatType::INFINITY)]),
+            vec![ItemPerMinute::new(iron_ingot, 30.0)],
+            game_db,
+        );
+        config.objective = ObjectiveMode::SinkPoints;
+
+        let error = solve(&config).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PlanError>(),
+            Some(&PlanError::UnboundedPlan)
+        );
+    }
+
+    #[test]
+    fn test_sink_points_with_max_power() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 0.0)], game_db);
+        config.objective = ObjectiveMode::SinkPoints;
+        let output_amount = |graph: &SolvedGraph| -> FloatType {
+            graph
+                .node_weights()
+                .filter_map(|n| match n {
+                    SolvedNodeWeight::Output(output) => Some(output.amount),
+                    _ => None,
+                })
+                .sum()
+        };
+
+        let result = solve(&config).unwrap();
+        assert!(output_amount(&result) > 1000.0);
+
+        // 4 Smelters at 4 MW each
+        config.max_power_mw = Some(16.0);
+        let result = solve(&config).unwrap();
+        assert_eq!(round(output_amount(&result), 3), 120.0);
+    }
+
+    #[test]
+    fn test_fuel_no_waste_unsolvable() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_LiquidFuel_C"]);
+
+        let fuel = game_db.find_item("Desc_LiquidFuel_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(fuel, 40.0)], game_db);
+        assert!(solve(&config).is_ok());
+
+        config.no_waste = true;
+        let error = solve(&config).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PlanError>(),
+            Some(&PlanError::UnsolvablePlan)
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronPlate_C",
+            "Recipe_IronRod_C",
+            "Recipe_Screw_C",
+            "Recipe_IronPlateReinforced_C",
+        ]);
+
+        let reinforced_plate = game_db.find_item("Desc_IronPlateReinforced_C").unwrap();
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let iron_rod = game_db.find_item("Desc_IronRod_C").unwrap();
+        let mut config = PlanConfig::new(
+            vec![ItemPerMinute::new(reinforced_plate, 5.0)],
+            game_db.clone(),
+        );
+        config.max_depth = Some(3);
+        assert!(solve(&config).is_ok());
+
+        // the only reinforced plate recipe is pruned once its inputs are out of reach
+        config.max_depth = Some(2);
+        let error = solve(&config).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PlanError>(),
+            Some(&PlanError::NoRecipeForOutput(
+                "Reinforced Iron Plate".into()
+            ))
+        );
+
+        // iron ingots and rods being available as inputs cuts the chains short enough
+        config.inputs.insert(iron_ingot, 100.0);
+        config.inputs.insert(iron_rod, 100.0);
+        assert!(solve(&config).is_ok());
+    }
+
+    #[test]
+    fn test_fuel_no_waste_with_polymer_resin_output() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_LiquidFuel_C"]);
+
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Output("Desc_LiquidFuel_C", 40.0)],
+                    1 [Output("Desc_PolymerResin_C", 30.0)],
+                    2 [Production("Recipe_LiquidFuel_C", 1.0)],
+                    3 [Input("Desc_LiquidOil_C", 60.0)]
+                ],
+                edges: [
+                    3 -> 2 ["Desc_LiquidOil_C", 60.0],
+                    2 -> 0 ["Desc_LiquidFuel_C", 40.0],
+                    2 -> 1 ["Desc_PolymerResin_C", 30.0]
+                ]
+            }
+        );
+
+        let fuel = game_db.find_item("Desc_LiquidFuel_C").unwrap();
+        let polymer_resin = game_db.find_item("Desc_PolymerResin_C").unwrap();
+        let mut config = PlanConfig::new(
+            vec![
+                ItemPerMinute::new(fuel, 40.0),
+                ItemPerMinute::new(polymer_resin, 30.0),
+            ],
+            game_db,
+        );
+        config.no_waste = true;
+
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
+    }
+
+    #[test]
+    fn test_reinforced_plate_no_surplus_iron_rods() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronPlate_C",
+            "Recipe_IronRod_C",
+            "Recipe_Screw_C",
+            "Recipe_IronPlateReinforced_C",
+        ]);
+
+        let reinforced_plate = game_db.find_item("Desc_IronPlateReinforced_C").unwrap();
+        let iron_rod = game_db.find_item("Desc_IronRod_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(reinforced_plate, 5.0)], game_db);
+        // 30 rods / min where the screws only need 15
+        config.pinned_recipes.insert("Recipe_IronRod_C".into(), 2.0);
+        let by_products = |graph: &SolvedGraph| -> Vec<(String, FloatType)> {
+            let mut by_products: Vec<(String, FloatType)> = graph
+                .node_weights()
+                .filter_map(|n| match n {
+                    SolvedNodeWeight::ByProduct(by_product) => {
+                        Some((by_product.item.key.clone(), round(by_product.amount, 3)))
+                    }
+                    _ => None,
+                })
+                .collect();
+            by_products.sort_by(|a, b| a.0.cmp(&b.0));
+            by_products
+        };
+
+        let result = solve(&config).unwrap();
+        assert_eq!(by_products(&result), vec![("Desc_IronRod_C".into(), 15.0)]);
+
+        // the surplus rods are made into screws instead
+        config.no_surplus_items = vec![iron_rod];
+        let result = solve(&config).unwrap();
+        assert_eq!(
+            by_products(&result),
+            vec![("Desc_IronScrew_C".into(), 60.0)]
+        );
+    }
+
+    #[test]
+    fn test_fuel_and_polymer_resin_prefer_joint_recipe() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_LiquidFuel_C",
+            "Recipe_Alternate_DilutedFuel_C",
+        ]);
+
+        let fuel = game_db.find_item("Desc_LiquidFuel_C").unwrap();
+        let polymer_resin = game_db.find_item("Desc_PolymerResin_C").unwrap();
+        let heavy_oil_residue = game_db.find_item("Desc_HeavyOilResidue_C").unwrap();
+        let coal = game_db.find_item("Desc_Coal_C").unwrap();
+        let mut config = PlanConfig::with_inputs(
+            HashMap::from([
+                (heavy_oil_residue, FloatType::INFINITY),
+                (polymer_resin.clone(), FloatType::INFINITY),
+            ]),
+            vec![ItemPerMinute::new(fuel.clone(), 40.0)],
+            game_db,
+        );
+        // none of the plans use coal, so they all cost the same
+        config.objective = ObjectiveMode::MinimizeResource;
+        config.target_resource = Some(coal);
+        let recipes = |graph: &SolvedGraph| -> Vec<String> {
+            graph
+                .node_weights()
+                .filter_map(|n| match n {
+                    SolvedNodeWeight::Production { recipe, .. } => Some(recipe.key.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        // 0.4 Blenders instead of 1 Refinery
+        let result = solve(&config).unwrap();
+        assert_eq!(recipes(&result), vec!["Recipe_Alternate_DilutedFuel_C"]);
+
+        config.outputs = vec![
+            ItemPerMinute::new(fuel, 40.0),
+            ItemPerMinute::new(polymer_resin, 30.0),
+        ];
+        let result = solve(&config).unwrap();
+        assert_eq!(recipes(&result), vec!["Recipe_LiquidFuel_C"]);
+    }
+
+    #[test]
+    fn test_iron_plate_keep_unused() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_Alternate_IngotIron_C",
+            "Recipe_IronPlate_C",
+        ]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        let iron_alloy_count = |graph: &SolvedGraph| {
+            graph.node_weights().find_map(|n| match n {
+                SolvedNodeWeight::Production {
+                    recipe,
+                    building_count,
+                    ..
+                } if recipe.key == "Recipe_Alternate_IngotIron_C" => Some(*building_count),
+                _ => None,
+            })
+        };
+
+        let result = solve(&config).unwrap();
+        assert_eq!(iron_alloy_count(&result), None);
+
+        config.keep_unused = true;
+        let result = solve(&config).unwrap();
+        assert_eq!(iron_alloy_count(&result), Some(0.0));
+        assert_eq!(
+            result.node_weights().filter(|n| n.is_production()).count(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_iron_plate_with_pinned_iron_ingot() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Output("Desc_IronPlate_C", 40.0)],
+                    1 [Production("Recipe_IronPlate_C", 2.0)],
+                    2 [Production("Recipe_IngotIron_C", 2.0)],
+                    3 [Input("Desc_OreIron_C", 60.0)]
+                ],
+                edges: [
+                    3 -> 2 ["Desc_OreIron_C", 60.0],
+                    2 -> 1 ["Desc_IronIngot_C", 60.0],
+                    1 -> 0 ["Desc_IronPlate_C", 40.0]
+                ]
+            }
+        );
+
+        // with no minimum output, the pinned smelters alone decide how many plates are made
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 0.0)], game_db);
+        config.objective = ObjectiveMode::SinkPoints;
+        config
+            .pinned_recipes
+            .insert("Recipe_IngotIron_C".into(), 2.0);
+
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
+    }
+
+    #[test]
+    fn test_iron_plate_with_pinned_iron_ingot_reduced_output() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Output("Desc_IronPlate_C", 30.0)],
+                    1 [Production("Recipe_IronPlate_C", 1.5)],
+                    2 [Production("Recipe_IngotIron_C", 2.0)],
+                    3 [Input("Desc_OreIron_C", 60.0)],
+                    4 [ByProduct("Desc_IronIngot_C", 15.0)]
+                ],
+                edges: [
+                    3 -> 2 ["Desc_OreIron_C", 60.0],
+                    2 -> 1 ["Desc_IronIngot_C", 45.0],
+                    2 -> 4 ["Desc_IronIngot_C", 15.0],
+                    1 -> 0 ["Desc_IronPlate_C", 30.0]
+                ]
+            }
+        );
+
+        // the smelters stay fixed while the plates are re-optimized for the smaller output
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 30.0)], game_db);
+        config
+            .pinned_recipes
+            .insert("Recipe_IngotIron_C".into(), 2.0);
+
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
+    }
+
+    #[test]
+    fn test_iron_plate_with_pinned_iron_ingot_unsolvable() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let mut config = PlanConfig::with_inputs(
+            HashMap::from([(iron_ore, 30.0)]),
+            vec![ItemPerMinute::new(iron_plate, 10.0)],
+            game_db,
+        );
+        config
+            .pinned_recipes
+            .insert("Recipe_IngotIron_C".into(), 2.0);
+
+        assert!(solve(&config).is_err());
+    }
+
+    #[test]
+    fn test_simplicity_uses_fewest_recipes() {
+        let mut game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+        let mut modded_recipe = game_db
+            .find_recipe("Recipe_IngotIron_C")
+            .unwrap()
+            .as_ref()
+            .clone();
+        modded_recipe.key = "Recipe_Modded_IngotIron_C".into();
+        modded_recipe.name = "Modded Iron Ingot".into();
+        game_db.recipes.push(Rc::new(modded_recipe));
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 90.0)], game_db);
+        config.objective = ObjectiveMode::Simplicity;
+
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        let building_counts: Vec<FloatType> = result
+            .node_weights()
+            .filter_map(|n| match n {
+                SolvedNodeWeight::Production { building_count, .. } => Some(*building_count),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(building_counts.len(), 1);
+        assert_eq!(round(building_counts[0], 3), 3.0);
+    }
+
+    #[test]
+    fn test_objective_value_minimize_resource() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        config.objective = ObjectiveMode::MinimizeResource;
+        config.target_resource = config.game_db.find_item("Desc_OreIron_C");
+
+        let solved_plan = solve_with_objective(&config, &mut FullPlanCache::default())
+            .unwrap_or_else(|e| {
+                panic!("Failed to solve plan: {}", e);
+            });
+        assert_eq!(round(solved_plan.objective_value, 3), 60.0);
+    }
+
+    #[test]
+    fn test_min_inputs_iron_ingot() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_Alternate_PureIronIngot_C",
+        ]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 65.0)], game_db);
+        let input_keys = |graph: &SolvedGraph| -> Vec<String> {
+            let mut keys: Vec<String> = graph
+                .node_weights()
+                .filter_map(|n| match n {
+                    SolvedNodeWeight::Input(input) => Some(input.item.key.clone()),
+                    _ => None,
+                })
+                .collect();
+            keys.sort();
+            keys
+        };
+
+        // pure iron ingots save iron ore by also using water
+        let result = solve(&config).unwrap();
+        assert_eq!(input_keys(&result), vec!["Desc_OreIron_C", "Desc_Water_C"]);
+
+        config.objective = ObjectiveMode::MinInputs;
+        let result = solve(&config).unwrap();
+        assert_eq!(input_keys(&result), vec!["Desc_OreIron_C"]);
+        let iron_ore_used = result
+            .node_weights()
+            .find_map(|n| match n {
+                SolvedNodeWeight::Input(input) => Some(input.amount),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(round(iron_ore_used, 3), 65.0);
+    }
+
+    #[test]
+    fn test_minimize_resource_caterium() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotCaterium_C",
+            "Recipe_IngotCopper_C",
+            "Recipe_Quickwire_C",
+            "Recipe_Alternate_Quickwire_C",
+        ]);
+
+        let quickwire = game_db.find_item("Desc_HighSpeedWire_C").unwrap();
+        let caterium_ore = game_db.find_item("Desc_OreGold_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(quickwire, 120.0)], game_db);
+        config.objective = ObjectiveMode::MinimizeResource;
+        config.target_resource = Some(Rc::clone(&caterium_ore));
+
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        let mut recipes: Vec<&str> = result
+            .node_weights()
+            .filter_map(|n| match n {
+                SolvedNodeWeight::Production { recipe, .. } => Some(recipe.key.as_str()),
+                _ => None,
+            })
+            .collect();
+        recipes.sort();
+        let caterium_used = result
+            .node_weights()
+            .find_map(|n| match n {
+                SolvedNodeWeight::Input(input) if input.item == caterium_ore => Some(input.amount),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(
+            recipes,
+            vec![
+                "Recipe_Alternate_Quickwire_C",
+                "Recipe_IngotCaterium_C",
+                "Recipe_IngotCopper_C"
+            ]
+        );
+        assert_eq!(round(caterium_used, 3), 30.0);
+    }
+
+    #[test]
+    fn test_iron_ingot_integer_buildings() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
 
-    macro_rules! graph_builder {
-        (
-            Graph($game_db:ident) {
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
                 nodes: [
-                    $($id:literal [$node_type:ident($($node_args:tt)*)]),*
+                    0 [Output("Desc_IronIngot_C", 65.0)],
+                    1 [ByProduct("Desc_IronIngot_C", 25.0)],
+                    2 [Production("Recipe_IngotIron_C", 3.0)],
+                    3 [Input("Desc_OreIron_C", 90.0)]
                 ],
                 edges: [
-                    $($source:literal -> $target:literal [$item:literal, $amount:expr]),*
+                    3 -> 2 ["Desc_OreIron_C", 90.0],
+                    2 -> 1 ["Desc_IronIngot_C", 25.0],
+                    2 -> 0 ["Desc_IronIngot_C", 65.0]
                 ]
             }
-        ) => {
-            {
-                let mut temp_graph = SolvedGraph::new();
-                let mut node_mappings: HashMap<u32, NodeIndex> = HashMap::new();
+        );
 
-                $(
-                    {
-                        let weight = graph_builder!(@node($game_db) $node_type($($node_args)*));
-                        if !node_mappings.insert($id, temp_graph.add_node(weight)).is_none() {
-                            panic!("Node id {} was used multiple times", $id);
-                        }
-                    }
-                )*
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 65.0)], game_db);
+        config.integer_buildings = true;
 
-                $(
-                    {
-                        let src_id = *node_mappings.get(&$source).unwrap_or_else(||
-                            panic!("Source Node {} does not exist", $source));
-                        let target_id = *node_mappings.get(&$target).unwrap_or_else(||
-                            panic!("Target Node {} does not exist", $target));
-                        let item = $game_db.find_item($item).unwrap_or_else(||
-                            panic!("Item {} does not exist", $item));
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
+    }
 
-                        temp_graph.add_edge(src_id, target_id, ItemPerMinute::new(item, $amount));
-                    }
-                )*
+    #[test]
+    fn test_sink_points_prefers_higher_point_output() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronPlate_C",
+            "Recipe_IronRod_C",
+            "Recipe_Screw_C",
+        ]);
 
-                temp_graph
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Output("Desc_IronScrew_C", 120.0)],
+                    1 [Production("Recipe_Screw_C", 3.0)],
+                    2 [Production("Recipe_IronRod_C", 2.0)],
+                    3 [Production("Recipe_IngotIron_C", 1.0)],
+                    4 [Input("Desc_OreIron_C", 30.0)]
+                ],
+                edges: [
+                    4 -> 3 ["Desc_OreIron_C", 30.0],
+                    3 -> 2 ["Desc_IronIngot_C", 30.0],
+                    2 -> 1 ["Desc_IronRod_C", 30.0],
+                    1 -> 0 ["Desc_IronScrew_C", 120.0]
+                ]
             }
-        };
-        (
-            @node($game_db:ident) Production($recipe: literal, $building_count:expr)
-        ) => {
-            SolvedNodeWeight::new_production(
-                $game_db.find_recipe($recipe).unwrap_or_else(||
-                    panic!("Recipe {} does not exist", $recipe)),
-                $building_count
-            )
-        };
-        (
-            @node($game_db:ident) Input($item:literal, $amount:expr)
-        ) => {
-            SolvedNodeWeight::new_input(
-                $game_db.find_item($item).unwrap_or_else(||
-                    panic!("Item {} does not exist", $item)),
-                $amount
-            )
-        };
-        (
-            @node($game_db:ident) Output($item:literal, $amount:expr)
-        ) => {
-            SolvedNodeWeight::new_output(
-                $game_db.find_item($item).unwrap_or_else(||
-                    panic!("Item {} does not exist", $item)),
-                $amount
-            )
-        };
-        (
-            @node($game_db:ident) ByProduct($item:literal, $amount:expr)
-        ) => {
-            SolvedNodeWeight::new_by_product(
-                $game_db.find_item($item).unwrap_or_else(||
-                    panic!("Item {} does not exist", $item)),
-                $amount
-            )
-        };
-        (
-            @node($game_db:ident) Producer($building: literal, $building_count:expr)
-        ) => {
-            SolvedNodeWeight::new_producer(
-                $game_db.find_building($building).unwrap_or_else(||
-                    panic!("Building {} does not exist", $building)),
-                $building_count
-            )
-        };
+        );
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let screw = game_db.find_item("Desc_IronScrew_C").unwrap();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let mut config = PlanConfig::with_inputs(
+            HashMap::from([(iron_ore, 30.0)]),
+            vec![
+                ItemPerMinute::new(iron_plate, 0.0),
+                ItemPerMinute::new(screw, 0.0),
+            ],
+            game_db,
+        );
+        config.objective = ObjectiveMode::SinkPoints;
 
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
     }
 
     #[test]
-    fn test_iron_ingot_base_recipes() {
-        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+    fn test_sink_points_with_output_weights() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronPlate_C",
+            "Recipe_IronRod_C",
+            "Recipe_Screw_C",
+        ]);
 
         let expected_graph = graph_builder!(
             Graph(game_db) {
                 nodes: [
-                    0 [Output("Desc_IronIngot_C", 30.0)],
-                    1 [Production("Recipe_IngotIron_C", 1.0)],
-                    2 [Input("Desc_OreIron_C", 30.0)]
+                    0 [Output("Desc_IronPlate_C", 20.0)],
+                    1 [Production("Recipe_IronPlate_C", 1.0)],
+                    2 [Production("Recipe_IngotIron_C", 1.0)],
+                    3 [Input("Desc_OreIron_C", 30.0)]
                 ],
                 edges: [
-                    2 -> 1 ["Desc_OreIron_C", 30.0],
-                    1 -> 0 ["Desc_IronIngot_C", 30.0]
+                    3 -> 2 ["Desc_OreIron_C", 30.0],
+                    2 -> 1 ["Desc_IronIngot_C", 30.0],
+                    1 -> 0 ["Desc_IronPlate_C", 20.0]
                 ]
             }
         );
 
-        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
-        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 30.0)], game_db);
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let screw = game_db.find_item("Desc_IronScrew_C").unwrap();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let mut config = PlanConfig::with_inputs(
+            HashMap::from([(iron_ore, 30.0)]),
+            vec![
+                ItemPerMinute::new(Rc::clone(&iron_plate), 0.0),
+                ItemPerMinute::new(screw, 0.0),
+            ],
+            game_db,
+        );
+        config.objective = ObjectiveMode::SinkPoints;
+        // plates are worth 4 points per ingot and screws 8, until plates count for more
+        config.output_weights.insert(iron_plate, 2.5);
 
         let result = solve(&config).unwrap_or_else(|e| {
             panic!("Failed to solve plan: {}", e);
@@ -279,30 +1840,26 @@ mod tests {
     }
 
     #[test]
-    fn test_iron_ingot_with_pure_ingot_recipe() {
-        let game_db = get_test_game_db_with_recipes(&[
-            "Recipe_IngotIron_C",
-            "Recipe_Alternate_PureIronIngot_C",
-        ]);
+    fn test_iron_ingot_round_to_whole_buildings() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
 
         let expected_graph = graph_builder!(
             Graph(game_db) {
                 nodes: [
                     0 [Output("Desc_IronIngot_C", 65.0)],
-                    1 [Production("Recipe_Alternate_PureIronIngot_C", 1.0)],
-                    2 [Input("Desc_OreIron_C", 35.0)],
-                    3 [Input("Desc_Water_C", 20.0)]
+                    1 [Production("Recipe_IngotIron_C", 3.0, 65.0 / 90.0 * 100.0)],
+                    2 [Input("Desc_OreIron_C", 65.0)]
                 ],
                 edges: [
-                    3 -> 1 ["Desc_Water_C", 20.0],
-                    2 -> 1 ["Desc_OreIron_C", 35.0],
+                    2 -> 1 ["Desc_OreIron_C", 65.0],
                     1 -> 0 ["Desc_IronIngot_C", 65.0]
                 ]
             }
         );
 
         let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
-        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 65.0)], game_db);
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 65.0)], game_db);
+        config.round_to_whole_buildings = true;
 
         let result = solve(&config).unwrap_or_else(|e| {
             panic!("Failed to solve plan: {}", e);
@@ -405,6 +1962,77 @@ mod tests {
         let config =
             PlanConfig::with_inputs(input_limits, vec![ItemPerMinute::new(wire, 232.5)], game_db);
 
+        let solved_plan = solve_with_objective(&config, &mut FullPlanCache::default())
+            .unwrap_or_else(|e| {
+                panic!("Failed to solve plan: {}", e);
+            });
+        assert_eq!(
+            solved_plan.saturated_resources,
+            vec!["Copper Ore", "Iron Ore"]
+        );
+        assert_graphs_equal(solved_plan.graph, expected_graph);
+    }
+
+    #[test]
+    fn saturated_resources_skip_non_resource_inputs() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        config.inputs.insert(Rc::clone(&iron_ingot), 30.0);
+
+        let solved_plan = solve_with_objective(&config, &mut FullPlanCache::default())
+            .unwrap_or_else(|e| {
+                panic!("Failed to solve plan: {}", e);
+            });
+        assert!(solved_plan.graph.node_weights().any(
+            |n| matches!(n, SolvedNodeWeight::Input(input) if input.item == iron_ingot && is_zero(input.amount - 30.0))
+        ));
+        assert!(solved_plan.saturated_resources.is_empty());
+    }
+
+    #[test]
+    fn test_fuel_and_plastic() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_Alternate_HeavyOilResidue_C",
+            "Recipe_ResidualFuel_C",
+            "Recipe_ResidualPlastic_C",
+        ]);
+
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Output("Desc_LiquidFuel_C", 180.0)],
+                    1 [Output("Desc_Plastic_C", 30.0)],
+                    2 [Production("Recipe_ResidualFuel_C", 4.5)],
+                    3 [Production("Recipe_ResidualPlastic_C", 1.5)],
+                    4 [Production("Recipe_Alternate_HeavyOilResidue_C", 6.75)],
+                    5 [ByProduct("Desc_PolymerResin_C", 45.0)],
+                    6 [Input("Desc_LiquidOil_C", 202.5)],
+                    7 [Input("Desc_Water_C", 30.0)]
+                ],
+                edges: [
+                    6 -> 4 ["Desc_LiquidOil_C", 202.5],
+                    4 -> 5 ["Desc_PolymerResin_C", 45.0],
+                    4 -> 2 ["Desc_HeavyOilResidue_C", 270.0],
+                    7 -> 3 ["Desc_Water_C", 30.0],
+                    4 -> 3 ["Desc_PolymerResin_C", 90.0],
+                    3 -> 1 ["Desc_Plastic_C", 30.0],
+                    2 -> 0 ["Desc_LiquidFuel_C", 180.0]
+                ]
+            }
+        );
+
+        let fuel = game_db.find_item("Desc_LiquidFuel_C").unwrap();
+        let plastic = game_db.find_item("Desc_Plastic_C").unwrap();
+        let config = PlanConfig::new(
+            vec![
+                ItemPerMinute::new(fuel, 180.0),
+                ItemPerMinute::new(plastic, 30.0),
+            ],
+            game_db,
+        );
         let result = solve(&config).unwrap_or_else(|e| {
             panic!("Failed to solve plan: {}", e);
         });
@@ -412,7 +2040,45 @@ mod tests {
     }
 
     #[test]
-    fn test_fuel_and_plastic() {
+    fn test_rubber_with_waste_penalty() {
+        let mut game_db = get_test_game_db_with_recipes(&[
+            "Recipe_Rubber_C",
+            "Recipe_Alternate_PolymerResin_C",
+            "Recipe_ResidualRubber_C",
+        ]);
+        // water as scarce as crude oil makes the residual rubber route cost more
+        let water = game_db.find_item("Desc_Water_C").unwrap();
+        game_db.resource_limits.insert(water, 11_700.0);
+
+        let rubber = game_db.find_item("Desc_Rubber_C").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(rubber, 40.0)], game_db);
+        let recipes = |graph: &SolvedGraph| -> Vec<String> {
+            let mut recipes: Vec<String> = graph
+                .node_weights()
+                .filter_map(|n| match n {
+                    SolvedNodeWeight::Production { recipe, .. } => Some(recipe.key.clone()),
+                    _ => None,
+                })
+                .collect();
+            recipes.sort();
+            recipes
+        };
+
+        // 40 heavy oil residue left over
+        let result = solve(&config).unwrap();
+        assert_eq!(recipes(&result), vec!["Recipe_Rubber_C"]);
+
+        // about 12.3 heavy oil residue left over
+        config.waste_penalty = 2.0;
+        let result = solve(&config).unwrap();
+        assert_eq!(
+            recipes(&result),
+            vec!["Recipe_Alternate_PolymerResin_C", "Recipe_ResidualRubber_C"]
+        );
+    }
+
+    #[test]
+    fn test_fuel_and_plastic_promote_by_products() {
         let game_db = get_test_game_db_with_recipes(&[
             "Recipe_Alternate_HeavyOilResidue_C",
             "Recipe_ResidualFuel_C",
@@ -427,7 +2093,7 @@ mod tests {
                     2 [Production("Recipe_ResidualFuel_C", 4.5)],
                     3 [Production("Recipe_ResidualPlastic_C", 1.5)],
                     4 [Production("Recipe_Alternate_HeavyOilResidue_C", 6.75)],
-                    5 [ByProduct("Desc_PolymerResin_C", 45.0)],
+                    5 [Output("Desc_PolymerResin_C", 45.0)],
                     6 [Input("Desc_LiquidOil_C", 202.5)],
                     7 [Input("Desc_Water_C", 30.0)]
                 ],
@@ -445,16 +2111,18 @@ mod tests {
 
         let fuel = game_db.find_item("Desc_LiquidFuel_C").unwrap();
         let plastic = game_db.find_item("Desc_Plastic_C").unwrap();
-        let config = PlanConfig::new(
+        let mut config = PlanConfig::new(
             vec![
                 ItemPerMinute::new(fuel, 180.0),
                 ItemPerMinute::new(plastic, 30.0),
             ],
             game_db,
         );
+        config.promote_byproducts_to_outputs = true;
         let result = solve(&config).unwrap_or_else(|e| {
             panic!("Failed to solve plan: {}", e);
         });
+        assert!(!result.node_weights().any(|n| n.is_by_product()));
         assert_graphs_equal(result, expected_graph);
     }
 
@@ -513,6 +2181,30 @@ mod tests {
         assert_graphs_equal(result, expected_graph);
     }
 
+    #[test]
+    fn test_recycled_rubber_plastic_loop_is_deterministic() {
+        let game_db = get_game_db_with_base_recipes_plus(&[
+            "Recipe_Alternate_HeavyOilResidue_C",
+            "Recipe_Alternate_DilutedFuel_C",
+            "Recipe_Alternate_Plastic_1_C",
+            "Recipe_Alternate_RecycledRubber_C",
+        ]);
+
+        let plastic = game_db.find_item("Desc_Plastic_C").unwrap();
+        let rubber = game_db.find_item("Desc_Rubber_C").unwrap();
+        let config = PlanConfig::new(
+            vec![
+                ItemPerMinute::new(plastic, 300.0),
+                ItemPerMinute::new(rubber, 300.0),
+            ],
+            game_db,
+        );
+
+        let first = solve(&config).unwrap();
+        let second = solve(&config).unwrap();
+        assert_graphs_equal(second, first);
+    }
+
     #[test]
     fn test_recycled_rubber_plastic_loop() {
         let game_db = get_game_db_with_base_recipes_plus(&[
@@ -622,6 +2314,33 @@ mod tests {
         assert_graphs_equal(result, expected_graph);
     }
 
+    #[test]
+    fn test_coal_power() {
+        let game_db = get_test_game_db_with_recipes(&[]);
+
+        let expected_graph = graph_builder!(
+            Graph(game_db) {
+                nodes: [
+                    0 [Generator("Desc_GeneratorCoal_C", "Desc_Coal_C", 32.0)],
+                    1 [Input("Desc_Coal_C", 480.0)],
+                    2 [Input("Desc_Water_C", 1440.0)]
+                ],
+                edges: [
+                    1 -> 0 ["Desc_Coal_C", 480.0],
+                    2 -> 0 ["Desc_Water_C", 1440.0]
+                ]
+            }
+        );
+
+        let mut config = PlanConfig::new(vec![], game_db);
+        config.power_output_mw = 2400.0;
+
+        let result = solve(&config).unwrap_or_else(|e| {
+            panic!("Failed to solve plan: {}", e);
+        });
+        assert_graphs_equal(result, expected_graph);
+    }
+
     fn assert_graphs_equal(actual: SolvedGraph, expected: SolvedGraph) {
         let mut node_mapping: HashMap<NodeIndex, NodeIndex> = HashMap::new();
 
@@ -654,7 +2373,7 @@ mod tests {
                 });
 
             assert!(
-                item_value_pair_equals(&actual[actual_edge], &edge.weight()),
+                item_value_pair_equals(&actual[actual_edge], edge.weight()),
                 "Mismatched weight for the edge connecting {:?} to {:?}. Expected: {:?}, actual: {:?}",
                 expected[edge.source()],
                 expected[edge.target()],
@@ -679,13 +2398,38 @@ mod tests {
                 item_value_pair_equals(a, b)
             }
             (
-                SolvedNodeWeight::Production(a_recipe, a_building_count),
-                SolvedNodeWeight::Production(b_recipe, b_building_count),
-            ) => a_recipe == b_recipe && float_equals(*a_building_count, *b_building_count),
+                SolvedNodeWeight::Production {
+                    recipe: a_recipe,
+                    building_count: a_building_count,
+                    clock_speed: a_clock_speed,
+                    power_mw: a_power,
+                    ..
+                },
+                SolvedNodeWeight::Production {
+                    recipe: b_recipe,
+                    building_count: b_building_count,
+                    clock_speed: b_clock_speed,
+                    power_mw: b_power,
+                    ..
+                },
+            ) => {
+                a_recipe == b_recipe
+                    && float_equals(*a_building_count, *b_building_count)
+                    && float_equals(*a_clock_speed, *b_clock_speed)
+                    && float_equals(*a_power, *b_power)
+            }
             (
                 SolvedNodeWeight::Producer(a_building, a_building_count),
                 SolvedNodeWeight::Producer(b_building, b_building_count),
             ) => a_building == b_building && float_equals(*a_building_count, *b_building_count),
+            (
+                SolvedNodeWeight::Generator(a_building, a_fuel, a_building_count),
+                SolvedNodeWeight::Generator(b_building, b_fuel, b_building_count),
+            ) => {
+                a_building == b_building
+                    && a_fuel.fuel.item == b_fuel.fuel.item
+                    && float_equals(*a_building_count, *b_building_count)
+            }
             _ => false,
         }
     }
@@ -0,0 +1,585 @@
+use petgraph::{
+    algo::toposort,
+    visit::EdgeRef,
+    Direction::{Incoming, Outgoing},
+};
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    game::{Building, GameDatabase, Item, ItemPerMinute},
+    utils::FloatType,
+};
+
+use super::{find_cycles, SolvedGraph, SolvedNodeWeight};
+
+#[derive(Debug, Clone, Default)]
+pub struct PlanSummary {
+    pub total_power_mw: FloatType,
+    pub total_power_generated_mw: FloatType,
+    /// Power generated minus power consumed, positive when the plan powers itself
+    pub net_power_mw: FloatType,
+    pub total_floor_area_m2: FloatType,
+    pub total_volume_m3: FloatType,
+    pub building_totals: HashMap<String, FloatType>,
+    pub input_totals: Vec<ItemPerMinute>,
+    pub output_totals: Vec<ItemPerMinute>,
+    pub by_product_totals: Vec<ItemPerMinute>,
+    pub total_sink_points_per_min: FloatType,
+    /// Outputs that are worth no sink points and so can't be sunk
+    pub unsinkable_outputs: Vec<Rc<Item>>,
+    pub has_cycles: bool,
+    /// The number of production nodes in the longest chain from an input to an output,
+    /// or `None` when the plan has cycles
+    pub max_depth: Option<u32>,
+}
+
+impl PlanSummary {
+    pub fn from_graph(graph: &SolvedGraph) -> Self {
+        let mut summary = Self {
+            has_cycles: !find_cycles(graph).is_empty(),
+            ..Default::default()
+        };
+
+        for i in graph.node_indices() {
+            match &graph[i] {
+                SolvedNodeWeight::Production {
+                    recipe,
+                    building_count,
+                    power_mw,
+                    ..
+                } => {
+                    summary.total_power_mw += power_mw;
+                    summary.add_buildings(recipe.building.name(), *building_count);
+                    summary.add_dimensions(&recipe.building, *building_count);
+                }
+                SolvedNodeWeight::Producer(building, building_count) => {
+                    summary.add_buildings(building.name(), *building_count);
+                    summary.add_dimensions(building, *building_count);
+                }
+                SolvedNodeWeight::Generator(building, _, building_count) => {
+                    summary.total_power_generated_mw +=
+                        building.as_power_generator().power_production_mw as FloatType
+                            * building_count;
+                    summary.add_buildings(building.name(), *building_count);
+                    summary.add_dimensions(building, *building_count);
+                }
+                SolvedNodeWeight::Input(input) => {
+                    add_item_total(&mut summary.input_totals, input);
+                }
+                SolvedNodeWeight::Output(output) => {
+                    add_item_total(&mut summary.output_totals, output);
+                    summary.total_sink_points_per_min +=
+                        output.amount * output.item.sink_points as FloatType;
+                    if output.item.sink_points == 0
+                        && !summary.unsinkable_outputs.contains(&output.item)
+                    {
+                        summary.unsinkable_outputs.push(Rc::clone(&output.item));
+                    }
+                }
+                SolvedNodeWeight::ByProduct(by_product) => {
+                    add_item_total(&mut summary.by_product_totals, by_product);
+                }
+            }
+        }
+
+        summary.net_power_mw = summary.total_power_generated_mw - summary.total_power_mw;
+        if !summary.has_cycles {
+            summary.max_depth = max_depth(graph);
+        }
+        summary
+    }
+
+    fn add_buildings(&mut self, building_name: &str, building_count: FloatType) {
+        *self
+            .building_totals
+            .entry(building_name.to_string())
+            .or_default() += building_count;
+    }
+
+    /// A partially utilized building still takes up the space of a whole one, and
+    /// buildings without dimensions take up none.
+    fn add_dimensions(&mut self, building: &Building, building_count: FloatType) {
+        self.total_floor_area_m2 += building.floor_area() * building_count.ceil();
+        self.total_volume_m3 += building.volume() * building_count.ceil();
+    }
+}
+
+/// The number of a building needed to extract a resource input
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractorCount {
+    pub building: String,
+    pub resource: Rc<Item>,
+    pub count: u32,
+    /// How much more of the resource the rounded up count of extractors could extract
+    pub spare_capacity_per_min: FloatType,
+}
+
+impl ExtractorCount {
+    fn new(
+        building: String,
+        resource: Rc<Item>,
+        amount: FloatType,
+        extraction_rate: FloatType,
+    ) -> Self {
+        let count = (amount / extraction_rate).ceil();
+        Self {
+            building,
+            resource,
+            count: count as u32,
+            spare_capacity_per_min: count * extraction_rate - amount,
+        }
+    }
+}
+
+/// Lists how many of each extractor able to extract a resource would be needed to supply
+/// the plan's resource inputs.  Every extractor for a resource is listed as an
+/// alternative.  Resource wells are counted as a whole, extracting the combined rate of
+/// their satellite extractors.
+pub fn extractor_counts(graph: &SolvedGraph, game_db: &GameDatabase) -> Vec<ExtractorCount> {
+    let mut extractor_counts = Vec::new();
+    for i in graph.node_indices() {
+        let input = match &graph[i] {
+            SolvedNodeWeight::Input(input) if input.item.resource => input,
+            _ => continue,
+        };
+
+        for building in &game_db.buildings {
+            match building.as_ref() {
+                Building::ResourceExtractor(re) if re.allowed_resources.contains(&input.item) => {
+                    extractor_counts.push(ExtractorCount::new(
+                        re.name.clone(),
+                        Rc::clone(&input.item),
+                        input.amount,
+                        re.extraction_rate,
+                    ));
+                }
+                Building::ResourceWell(rw) if rw.allowed_resources.contains(&input.item) => {
+                    extractor_counts.push(ExtractorCount::new(
+                        rw.name.clone(),
+                        Rc::clone(&input.item),
+                        input.amount,
+                        rw.total_extraction_rate(),
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    extractor_counts
+}
+
+/// How much of a resource input ends up in an output or by-product
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceCost {
+    pub resource: Rc<Item>,
+    pub output: Rc<Item>,
+    pub amount: FloatType,
+}
+
+/// Splits each resource input between the outputs and by-products it ends up in, dividing
+/// the items leaving every node between its edges in proportion to their amounts.  A
+/// by-product that is consumed downstream passes its share on like any other node.
+/// Resources burned by generators aren't attributed to anything.  Returns `None` when the
+/// plan has cycles.
+pub fn resource_costs(graph: &SolvedGraph) -> Option<Vec<ResourceCost>> {
+    let sorted = toposort(graph, None).ok()?;
+
+    // the share of each node's outgoing items that ends up in each output item
+    let mut shares: HashMap<_, HashMap<Rc<Item>, FloatType>> = HashMap::new();
+    for i in sorted.into_iter().rev() {
+        let node_shares = match &graph[i] {
+            SolvedNodeWeight::Output(output) => HashMap::from([(Rc::clone(&output.item), 1.0)]),
+            SolvedNodeWeight::ByProduct(by_product)
+                if graph.edges_directed(i, Outgoing).next().is_none() =>
+            {
+                HashMap::from([(Rc::clone(&by_product.item), 1.0)])
+            }
+            _ => {
+                let total: FloatType = graph
+                    .edges_directed(i, Outgoing)
+                    .map(|e| e.weight().amount)
+                    .sum();
+                let mut node_shares = HashMap::new();
+                // nothing leaves a node whose outgoing edges were all rounded to zero
+                if total > 0.0 {
+                    for edge in graph.edges_directed(i, Outgoing) {
+                        for (item, share) in &shares[&edge.target()] {
+                            *node_shares.entry(Rc::clone(item)).or_default() +=
+                                share * edge.weight().amount / total;
+                        }
+                    }
+                }
+                node_shares
+            }
+        };
+        shares.insert(i, node_shares);
+    }
+
+    let mut costs = Vec::new();
+    for i in graph.node_indices() {
+        if let SolvedNodeWeight::Input(input) = &graph[i] {
+            if !input.item.resource {
+                continue;
+            }
+
+            for (output, share) in &shares[&i] {
+                costs.push(ResourceCost {
+                    resource: Rc::clone(&input.item),
+                    output: Rc::clone(output),
+                    amount: input.amount * share,
+                });
+            }
+        }
+    }
+
+    costs.sort_by(|a, b| {
+        (&a.resource.name, &a.output.name).cmp(&(&b.resource.name, &b.output.name))
+    });
+    Some(costs)
+}
+
+fn max_depth(graph: &SolvedGraph) -> Option<u32> {
+    let sorted = toposort(graph, None).ok()?;
+    let mut depths = HashMap::new();
+    for i in sorted {
+        let parent_depth = graph
+            .neighbors_directed(i, Incoming)
+            .map(|parent| depths[&parent])
+            .max()
+            .unwrap_or(0);
+        let depth = match graph[i] {
+            SolvedNodeWeight::Production { .. } => parent_depth + 1,
+            _ => parent_depth,
+        };
+        depths.insert(i, depth);
+    }
+
+    Some(depths.into_values().max().unwrap_or(0))
+}
+
+fn add_item_total(totals: &mut Vec<ItemPerMinute>, value: &ItemPerMinute) {
+    match totals.iter_mut().find(|t| t.item == value.item) {
+        Some(total) => total.amount += value.amount,
+        None => totals.push(value.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{test::get_test_game_db_with_recipes, ItemPerMinute},
+        plan::{solve, PlanConfig},
+        utils::round,
+    };
+
+    #[test]
+    fn total_power_mw() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        // 2 Constructors at 4 MW and 2 Smelters at 4 MW
+        let summary = PlanSummary::from_graph(&graph);
+        assert_eq!(round(summary.total_power_mw, 3), 16.0);
+    }
+
+    #[test]
+    fn max_depth_iron_plate() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 40.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let summary = PlanSummary::from_graph(&graph);
+        assert_eq!(summary.max_depth, Some(2));
+    }
+
+    #[test]
+    fn net_power_mw() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let smelter = game_db.find_building("Smelter").unwrap();
+        let coal_generator = game_db.find_building("Coal Generator").unwrap();
+        let mut config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 30.0)], game_db);
+        config.power_output_mw = 75.0;
+        let graph = solve(&config).unwrap();
+
+        // 1 Coal Generator at 75 MW powering 1 Smelter at 4 MW
+        let summary = PlanSummary::from_graph(&graph);
+        assert_eq!(round(summary.total_power_generated_mw, 3), 75.0);
+        assert_eq!(round(summary.net_power_mw, 3), 71.0);
+        assert_eq!(
+            round(summary.total_floor_area_m2, 3),
+            round(smelter.floor_area() + coal_generator.floor_area(), 3)
+        );
+        assert!(coal_generator.floor_area() > 0.0);
+    }
+
+    #[test]
+    fn building_and_item_totals() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate.clone(), 40.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let summary = PlanSummary::from_graph(&graph);
+        assert_eq!(
+            summary.building_totals,
+            HashMap::from([
+                ("Smelter".to_string(), 2.0),
+                ("Constructor".to_string(), 2.0)
+            ])
+        );
+        assert_eq!(
+            summary.input_totals,
+            vec![ItemPerMinute::new(iron_ore, 60.0)]
+        );
+        assert_eq!(
+            summary.output_totals,
+            vec![ItemPerMinute::new(iron_plate, 40.0)]
+        );
+        assert!(summary.by_product_totals.is_empty());
+        assert!(!summary.has_cycles);
+    }
+
+    #[test]
+    fn total_sink_points() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let config = PlanConfig::new(
+            vec![
+                ItemPerMinute::new(iron_plate.clone(), 40.0),
+                ItemPerMinute::new(iron_ingot.clone(), 30.0),
+            ],
+            game_db,
+        );
+        let graph = solve(&config).unwrap();
+
+        let summary = PlanSummary::from_graph(&graph);
+        assert_eq!(
+            summary.total_sink_points_per_min,
+            40.0 * iron_plate.sink_points as FloatType + 30.0 * iron_ingot.sink_points as FloatType
+        );
+        assert!(summary.unsinkable_outputs.is_empty());
+    }
+
+    #[test]
+    fn unsinkable_outputs() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_LiquidFuel_C"]);
+
+        let fuel = game_db.find_item("Desc_LiquidFuel_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(fuel.clone(), 40.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let summary = PlanSummary::from_graph(&graph);
+        assert_eq!(summary.total_sink_points_per_min, 0.0);
+        assert_eq!(summary.unsinkable_outputs, vec![fuel]);
+    }
+
+    #[test]
+    fn extractors_for_iron_ore() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 100.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let counts = extractor_counts(&graph, &config.game_db);
+        let expected = |building: &str, count, spare_capacity_per_min| ExtractorCount {
+            building: building.to_string(),
+            resource: iron_ore.clone(),
+            count,
+            spare_capacity_per_min,
+        };
+        assert_eq!(
+            counts,
+            vec![
+                expected("Miner Mk.1", 3, 30.0),
+                expected("Miner Mk.2", 2, 90.0),
+                expected("Miner Mk.3", 1, 90.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn extractor_spare_capacity() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C"]);
+
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_ingot, 33.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let counts = extractor_counts(&graph, &config.game_db);
+        assert_eq!(counts[0].building, "Miner Mk.1");
+        assert_eq!(counts[0].count, 1);
+        assert_eq!(round(counts[0].spare_capacity_per_min, 3), 27.0);
+    }
+
+    #[test]
+    fn extractors_for_crude_oil() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_LiquidFuel_C"]);
+
+        let fuel = game_db.find_item("Desc_LiquidFuel_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(fuel, 200.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        let mut counts: Vec<(String, u32)> = extractor_counts(&graph, &config.game_db)
+            .into_iter()
+            .map(|c| (c.building, c.count))
+            .collect();
+        counts.sort();
+        assert_eq!(
+            counts,
+            vec![
+                ("Oil Extractor".to_string(), 3),
+                ("Resource Well Pressurizer".to_string(), 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn resource_wells_for_nitrogen() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronPlate_C",
+            "Recipe_NitricAcid_C",
+        ]);
+
+        let nitric_acid = game_db.find_item("Desc_NitricAcid_C").unwrap();
+        let nitrogen = game_db.find_item("Desc_NitrogenGas_C").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(nitric_acid, 45.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        // 180 m^3 of nitrogen from wells with a single 60 m^3 / min satellite
+        let counts: Vec<ExtractorCount> = extractor_counts(&graph, &config.game_db)
+            .into_iter()
+            .filter(|c| c.resource == nitrogen)
+            .collect();
+        assert_eq!(
+            counts,
+            vec![ExtractorCount {
+                building: "Resource Well Pressurizer".to_string(),
+                resource: nitrogen,
+                count: 3,
+                spare_capacity_per_min: 0.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn resource_costs_iron_rods_and_plates() {
+        let game_db = get_test_game_db_with_recipes(&[
+            "Recipe_IngotIron_C",
+            "Recipe_IronPlate_C",
+            "Recipe_IronRod_C",
+        ]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let iron_rod = game_db.find_item("Desc_IronRod_C").unwrap();
+        let config = PlanConfig::new(
+            vec![
+                ItemPerMinute::new(iron_rod, 30.0),
+                ItemPerMinute::new(iron_plate, 60.0),
+            ],
+            game_db,
+        );
+        let graph = solve(&config).unwrap();
+
+        let costs = resource_costs(&graph).unwrap();
+        let costs: Vec<(&str, &str, FloatType)> = costs
+            .iter()
+            .map(|c| {
+                (
+                    c.resource.key.as_str(),
+                    c.output.key.as_str(),
+                    round(c.amount, 3),
+                )
+            })
+            .collect();
+        assert_eq!(
+            costs,
+            vec![
+                ("Desc_OreIron_C", "Desc_IronPlate_C", 90.0),
+                ("Desc_OreIron_C", "Desc_IronRod_C", 30.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn resource_costs_through_consumed_by_product() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_ore = game_db.find_item("Desc_OreIron_C").unwrap();
+        let iron_ingot = game_db.find_item("Desc_IronIngot_C").unwrap();
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let smelter_recipe = game_db.find_recipe("Recipe_IngotIron_C").unwrap();
+        let constructor_recipe = game_db.find_recipe("Recipe_IronPlate_C").unwrap();
+
+        // the smelter's ingots pass through a by-product node on the way to the plates
+        let mut graph = SolvedGraph::new();
+        let input = graph.add_node(SolvedNodeWeight::new_input(iron_ore.clone(), 30.0));
+        let smelter = graph.add_node(SolvedNodeWeight::new_production(smelter_recipe, 1.0));
+        let by_product = graph.add_node(SolvedNodeWeight::new_by_product(iron_ingot.clone(), 30.0));
+        let constructor = graph.add_node(SolvedNodeWeight::new_production(constructor_recipe, 1.5));
+        let output = graph.add_node(SolvedNodeWeight::new_output(iron_plate.clone(), 20.0));
+        graph.add_edge(input, smelter, ItemPerMinute::new(iron_ore.clone(), 30.0));
+        graph.add_edge(
+            smelter,
+            by_product,
+            ItemPerMinute::new(iron_ingot.clone(), 30.0),
+        );
+        graph.add_edge(
+            by_product,
+            constructor,
+            ItemPerMinute::new(iron_ingot.clone(), 30.0),
+        );
+        graph.add_edge(
+            constructor,
+            output,
+            ItemPerMinute::new(iron_plate.clone(), 20.0),
+        );
+
+        assert_eq!(
+            resource_costs(&graph).unwrap(),
+            vec![ResourceCost {
+                resource: iron_ore,
+                output: iron_plate,
+                amount: 30.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn total_floor_area_and_volume() {
+        let game_db = get_test_game_db_with_recipes(&["Recipe_IngotIron_C", "Recipe_IronPlate_C"]);
+
+        let iron_plate = game_db.find_item("Desc_IronPlate_C").unwrap();
+        let smelter = game_db.find_building("Smelter").unwrap();
+        let constructor = game_db.find_building("Constructor").unwrap();
+        let config = PlanConfig::new(vec![ItemPerMinute::new(iron_plate, 30.0)], game_db);
+        let graph = solve(&config).unwrap();
+
+        // 1.5 Constructors and 1.5 Smelters both round up to 2 whole buildings
+        let summary = PlanSummary::from_graph(&graph);
+        assert_eq!(
+            round(summary.total_floor_area_m2, 3),
+            round(2.0 * (smelter.floor_area() + constructor.floor_area()), 3)
+        );
+        assert_eq!(
+            round(summary.total_volume_m3, 3),
+            round(2.0 * (smelter.volume() + constructor.volume()), 3)
+        );
+        assert!(summary.total_floor_area_m2 > 0.0);
+    }
+}
@@ -11,7 +11,11 @@ pub fn round(value: FloatType, decimals: u8) -> FloatType {
 }
 
 pub fn clamp_to_zero(value: FloatType) -> FloatType {
-    if value.abs() < EPSILON {
+    clamp_to_zero_within(value, EPSILON)
+}
+
+pub fn clamp_to_zero_within(value: FloatType, epsilon: FloatType) -> FloatType {
+    if is_zero_within(value, epsilon) {
         0.0
     } else {
         value
@@ -19,5 +23,50 @@ pub fn clamp_to_zero(value: FloatType) -> FloatType {
 }
 
 pub fn is_zero(value: FloatType) -> bool {
-    value.abs() < EPSILON
+    is_zero_within(value, EPSILON)
+}
+
+pub fn is_zero_within(value: FloatType, epsilon: FloatType) -> bool {
+    value.abs() < epsilon
+}
+
+/// The Levenshtein distance between two strings, compared case-insensitively
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let substitution = previous[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            current[j] = substitution.min(previous[j] + 1).min(current[j - 1] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// The candidates closest to `name`, at most three of them and only those close enough
+/// to plausibly be a typo of it
+pub fn closest_matches<'a, I>(name: &str, candidates: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = (name.chars().count() / 4).max(2);
+    let mut matches: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    matches.sort();
+    matches.dedup_by(|a, b| a.1 == b.1);
+
+    matches
+        .into_iter()
+        .take(3)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
 }